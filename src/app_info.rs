@@ -20,11 +20,23 @@
 // https://github.com/michikora/Wox.Plugin.SteamLAUNCHER/blob/master/launcher.py
 // https://github.com/SkaceKamen/Wox.Plugin.Steam/blob/master/WoxSteam/Game.cs
 
+use crate::categories::{Category, Genre};
+use crate::controller::{ControllerSupport, SteamInput};
+use crate::deck::DeckCompatibility;
+use crate::depot::{Branch, Depot};
+use crate::languages::Language;
+use crate::platform::Platform;
+use crate::state::AppInfoState;
+use crate::steam_dir::steam_dir;
+use crate::vr::VrSupport;
 use anyhow::{anyhow, Error};
+use chrono::{DateTime, Utc};
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::convert::TryInto;
 use std::fs;
 use std::io::Read;
+use std::str::FromStr;
 
 fn u8(buf: &[u8], pos: &mut usize) -> u8 {
     let value = buf[*pos];
@@ -58,6 +70,53 @@ pub enum Property {
     String(String),
 }
 
+// Lookups of values that are stored as strings or numbers depending on the
+// app, used by the typed views of appinfo sections.
+pub(crate) fn string_prop(props: &HashMap<String, Property>, key: &str) -> Option<String> {
+    match props.get(key) {
+        Some(Property::String(string)) if !string.is_empty() => Some(string.to_owned()),
+        _ => None,
+    }
+}
+
+pub(crate) fn u64_prop(props: &HashMap<String, Property>, key: &str) -> Option<u64> {
+    match props.get(key) {
+        Some(Property::Uint32(uint32)) => Some(*uint32 as u64),
+        Some(Property::Uint64(uint64)) => Some(*uint64),
+        Some(Property::String(string)) => u64::from_str(string.trim()).ok(),
+        _ => None,
+    }
+}
+
+pub(crate) fn u32_prop(props: &HashMap<String, Property>, key: &str) -> Option<u32> {
+    match props.get(key) {
+        Some(Property::Uint32(uint32)) => Some(*uint32),
+        Some(Property::String(string)) => u32::from_str(string.trim()).ok(),
+        _ => None,
+    }
+}
+
+// Lists are stored as maps keyed by "0", "1", ... in no particular order.
+pub(crate) fn id_list(props: &HashMap<String, Property>) -> Vec<u32> {
+    let mut entries: Vec<(usize, u32)> = props
+        .keys()
+        .filter_map(|key| {
+            let index = usize::from_str(key).unwrap_or(usize::MAX);
+            u32_prop(props, key).map(|id| (index, id))
+        })
+        .collect();
+    entries.sort_unstable();
+    entries.into_iter().map(|(_, id)| id).collect()
+}
+
+pub(crate) fn bool_prop(props: &HashMap<String, Property>, key: &str) -> bool {
+    match props.get(key) {
+        Some(Property::Uint32(uint32)) => *uint32 != 0,
+        Some(Property::String(string)) => string == "1" || string.eq_ignore_ascii_case("true"),
+        _ => false,
+    }
+}
+
 #[derive(Debug)]
 pub struct AppInfo {
     pub state: u32,
@@ -71,8 +130,7 @@ pub struct AppInfo {
 impl AppInfo {
     pub fn load() -> Result<Vec<AppInfo>, Error> {
         let mut buf = Vec::new();
-        fs::File::open("c:/program files (x86)/steam/appcache/appinfo.vdf")?
-            .read_to_end(&mut buf)?;
+        fs::File::open(steam_dir().join("appcache/appinfo.vdf"))?.read_to_end(&mut buf)?;
         let mut pos = 0;
         let version = u8(&buf, &mut pos);
         // Doc only knows about 24 and 26. My file has 27. What other diffs are there?
@@ -107,6 +165,10 @@ impl AppInfo {
         }
         Ok(app_infos)
     }
+    pub fn info_state(&self) -> AppInfoState {
+        AppInfoState::from_bits_retain(self.state)
+    }
+
     pub fn print_props(&self, depth: usize) {
         self.print_props_helper(&self.props, depth, &"".to_owned());
     }
@@ -135,6 +197,13 @@ impl AppInfo {
         }
     }
 
+    pub fn map_entry(&self, path: &[&str]) -> Option<&HashMap<String, Property>> {
+        match self.entry(path) {
+            Some(Property::Map(map)) => Some(map),
+            _ => None,
+        }
+    }
+
     pub fn string_entry(&self, path: &[&str]) -> Option<String> {
         match self.entry(path) {
             Some(Property::String(string)) => Some(string.to_owned()),
@@ -176,6 +245,252 @@ impl AppInfo {
         }
     }
 
+    pub fn depots(&self) -> Vec<Depot> {
+        let mut depots = Vec::new();
+        if let Some(entries) = self.map_entry(&["appinfo", "depots"]) {
+            for (key, value) in entries {
+                // Besides the depots themselves, this section also holds
+                // entries like `branches` and `baselanguages`.
+                if let (Ok(id), Property::Map(props)) = (u32::from_str(key), value) {
+                    depots.push(Depot::from_props(id, props));
+                }
+            }
+        }
+        depots.sort_unstable_by_key(|d| d.id);
+        depots
+    }
+
+    pub fn parent(&self) -> Option<u32> {
+        self.u32_entry(&["appinfo", "common", "parent"])
+            .or_else(|| {
+                self.string_entry(&["appinfo", "common", "parent"])
+                    .and_then(|parent| u32::from_str(&parent).ok())
+            })
+    }
+
+    /// Ids of this app's DLC, from `extended/listofdlc` and the `dlcappid`
+    /// of its depots.
+    pub fn dlc(&self) -> Vec<u32> {
+        let mut dlc: Vec<u32> = self
+            .string_entry(&["appinfo", "extended", "listofdlc"])
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|id| u32::from_str(id.trim()).ok())
+            .collect();
+        dlc.extend(self.depots().iter().filter_map(|d| d.dlc_app_id));
+        dlc.sort_unstable();
+        dlc.dedup();
+        dlc
+    }
+
+    /// `common/category` is keyed by "category_<id>".
+    pub fn categories(&self) -> Vec<Category> {
+        let mut ids: Vec<u32> = self
+            .map_entry(&["appinfo", "common", "category"])
+            .map(|categories| {
+                categories
+                    .keys()
+                    .filter_map(|key| key.strip_prefix("category_"))
+                    .filter_map(|id| u32::from_str(id).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        ids.sort_unstable();
+        ids.into_iter().map(Category::from_id).collect()
+    }
+
+    pub fn genres(&self) -> Vec<Genre> {
+        self.map_entry(&["appinfo", "common", "genres"])
+            .map(id_list)
+            .unwrap_or_default()
+            .into_iter()
+            .map(Genre::from_id)
+            .collect()
+    }
+
+    pub fn primary_genre(&self) -> Option<Genre> {
+        self.common_u32("primary_genre").map(Genre::from_id)
+    }
+
+    pub fn deck_compatibility(&self) -> Option<DeckCompatibility> {
+        self.map_entry(&["appinfo", "common", "steam_deck_compatibility"])
+            .map(DeckCompatibility::from_props)
+    }
+
+    /// Names from `common/associations` of the given type ("developer",
+    /// "publisher" or "franchise"). Older entries only have the
+    /// `extended/developer` and `extended/publisher` strings.
+    pub fn associations(&self, kind: &str) -> Vec<String> {
+        let mut entries: Vec<(usize, String)> = Vec::new();
+        if let Some(associations) = self.map_entry(&["appinfo", "common", "associations"]) {
+            for (key, association) in associations {
+                if let Property::Map(association) = association {
+                    if string_prop(association, "type").as_deref() != Some(kind) {
+                        continue;
+                    }
+                    if let Some(name) = string_prop(association, "name") {
+                        entries.push((usize::from_str(key).unwrap_or(usize::MAX), name));
+                    }
+                }
+            }
+        }
+        entries.sort_unstable();
+        let mut names: Vec<String> = entries.into_iter().map(|(_, name)| name).collect();
+        if names.is_empty() {
+            if let Some(name) = self.string_entry(&["appinfo", "extended", kind]) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    pub fn release_date(&self) -> Option<DateTime<Utc>> {
+        self.common_u32("steam_release_date")
+            .and_then(|secs| DateTime::from_timestamp(secs as i64, 0))
+    }
+
+    pub fn original_release_date(&self) -> Option<DateTime<Utc>> {
+        self.common_u32("original_release_date")
+            .and_then(|secs| DateTime::from_timestamp(secs as i64, 0))
+    }
+
+    pub fn metacritic_score(&self) -> Option<u32> {
+        self.common_u32("metacritic_score")
+    }
+
+    pub fn review_score(&self) -> Option<u32> {
+        self.common_u32("review_score")
+    }
+
+    pub fn review_percentage(&self) -> Option<u32> {
+        self.common_u32("review_percentage")
+    }
+
+    // internal helper
+    fn common_u32(&self, key: &str) -> Option<u32> {
+        self.map_entry(&["appinfo", "common"])
+            .and_then(|common| u32_prop(common, key))
+            .filter(|value| *value != 0)
+    }
+
+    pub fn localized_name(&self, language: &str) -> Option<String> {
+        self.string_entry(&["appinfo", "common", "name_localized", language])
+            .filter(|name| !name.is_empty())
+    }
+
+    pub fn languages(&self) -> Vec<Language> {
+        let mut languages: Vec<Language> = Vec::new();
+        if let Some(supported) = self.map_entry(&["appinfo", "common", "supported_languages"]) {
+            for (name, props) in supported {
+                if let Property::Map(props) = props {
+                    languages.push(Language::from_props(name, props));
+                }
+            }
+        } else if let Some(legacy) = self.map_entry(&["appinfo", "common", "languages"]) {
+            for name in legacy.keys() {
+                languages.push(Language::from_legacy(name, legacy));
+            }
+        }
+        languages.retain(|l| l.supported);
+        languages.sort_unstable_by(|l1, l2| l1.name.cmp(&l2.name));
+        languages
+    }
+
+    /// Apps without an `oslist` are Windows only.
+    pub fn platforms(&self) -> Vec<Platform> {
+        let mut platforms: Vec<Platform> = self
+            .string_entry(&["appinfo", "common", "oslist"])
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|os| Platform::from_str(os).ok())
+            .collect();
+        platforms.dedup();
+        if platforms.is_empty() {
+            platforms.push(Platform::Windows);
+        }
+        platforms
+    }
+
+    pub fn os_arch(&self) -> Option<String> {
+        self.string_entry(&["appinfo", "common", "osarch"])
+            .filter(|arch| !arch.is_empty())
+    }
+
+    /// Falls back to the "Full/Partial Controller Support" categories when
+    /// `common/controller_support` is missing.
+    pub fn controller_support(&self) -> ControllerSupport {
+        if let Some(support) = self.string_entry(&["appinfo", "common", "controller_support"]) {
+            if let Ok(support) = ControllerSupport::from_str(&support) {
+                return support;
+            }
+        }
+        let categories = self.categories();
+        if categories.iter().any(|c| c.id == 28) {
+            ControllerSupport::Full
+        } else if categories.iter().any(|c| c.id == 18) {
+            ControllerSupport::Partial
+        } else {
+            ControllerSupport::None
+        }
+    }
+
+    pub fn steam_input(&self) -> SteamInput {
+        self.map_entry(&["appinfo", "config"])
+            .map(SteamInput::from_props)
+            .unwrap_or_default()
+    }
+
+    pub fn vr_support(&self) -> VrSupport {
+        let common = self.map_entry(&["appinfo", "common"]);
+        let flag = |key: &str| common.map(|c| bool_prop(c, key)).unwrap_or(false);
+        let categories = self.categories();
+        let has_category = |id: u32| categories.iter().any(|c| c.id == id);
+        VrSupport {
+            openvr: flag("openvrsupport"),
+            oculus: flag("oculussupport"),
+            vr_supported: has_category(31) || has_category(53),
+            tracked_controllers: has_category(52),
+            vr_only: flag("onlyvrsupport") || has_category(54),
+        }
+    }
+
+    pub fn client_icon(&self) -> Option<String> {
+        self.string_entry(&["appinfo", "common", "clienticon"])
+            .filter(|hash| !hash.is_empty())
+    }
+
+    pub fn store_tags(&self) -> Vec<u32> {
+        self.map_entry(&["appinfo", "common", "store_tags"])
+            .map(id_list)
+            .unwrap_or_default()
+    }
+
+    pub fn branches(&self) -> Vec<Branch> {
+        let mut branches = Vec::new();
+        if let Some(entries) = self.map_entry(&["appinfo", "depots", "branches"]) {
+            for (name, value) in entries {
+                if let Property::Map(props) = value {
+                    branches.push(Branch::from_props(name, props));
+                }
+            }
+        }
+        // Most recently pushed branches first.
+        branches.sort_unstable_by_key(|b| Reverse(b.time_updated));
+        branches
+    }
+
+    /// Estimate how many bytes installing this app would take for the given
+    /// platform, language and branch. DLC depots and depots shared from
+    /// other apps (redistributables and the like) are not counted.
+    pub fn download_size(&self, os: &str, arch: &str, language: &str, branch: &str) -> u64 {
+        self.depots()
+            .iter()
+            .filter(|d| d.dlc_app_id.is_none() && d.depot_from_app.is_none())
+            .filter(|d| d.applies_to(os, arch, language))
+            .filter_map(|d| d.size(branch))
+            .sum()
+    }
+
     pub fn entry(&self, path: &[&str]) -> Option<&Property> {
         let mut props = &self.props;
         let mut value = None;
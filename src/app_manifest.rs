@@ -0,0 +1,180 @@
+// Parser for steamapps/appmanifest_<id>.acf, the per library record of an
+// installed app.
+//
+//   "AppState"
+//   {
+//       "appid"         "220"
+//       "installdir"    "Half-Life 2"
+//       "SizeOnDisk"    "4187000000"
+//       "buildid"       "4104489"
+//       "StateFlags"    "4"
+//       "InstalledDepots" { "221" { "manifest" "..." "size" "..." } }
+//       "UserConfig" { "language" "english" "betakey" "beta" }
+//       ...
+//   }
+
+use crate::state::AppState;
+use crate::vdf::{Property, Vdf};
+use anyhow::{anyhow, Error};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum AutoUpdateBehavior {
+    KeepUpdated,
+    UpdateOnLaunch,
+    HighPriority,
+    Unknown(u32),
+}
+
+impl From<u32> for AutoUpdateBehavior {
+    fn from(value: u32) -> AutoUpdateBehavior {
+        match value {
+            0 => AutoUpdateBehavior::KeepUpdated,
+            1 => AutoUpdateBehavior::UpdateOnLaunch,
+            2 => AutoUpdateBehavior::HighPriority,
+            _ => AutoUpdateBehavior::Unknown(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstalledDepot {
+    pub depot_id: u32,
+    pub manifest: u64,
+    pub size: u64,
+    pub dlc_app_id: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppManifest {
+    pub app_id: u32,
+    pub name: Option<String>,
+    pub install_dir: String,
+    pub size_on_disk: u64,
+    pub build_id: u32,
+    pub state_flags: u32,
+    pub last_updated: u32,
+    pub last_owner: Option<u64>,
+    pub bytes_to_download: u64,
+    pub bytes_downloaded: u64,
+    pub bytes_to_stage: u64,
+    pub bytes_staged: u64,
+    pub auto_update_behavior: AutoUpdateBehavior,
+    pub installed_depots: Vec<InstalledDepot>,
+    pub user_config: HashMap<String, String>,
+    pub mounted_config: HashMap<String, String>,
+    // The acf file itself and the steamapps folder it was found in.
+    pub path: PathBuf,
+    pub library: PathBuf,
+}
+
+fn string_map(map: Option<&HashMap<String, Property>>) -> HashMap<String, String> {
+    let mut strings = HashMap::new();
+    if let Some(map) = map {
+        for (key, value) in map {
+            if let Property::String(value) = value {
+                strings.insert(key.to_lowercase(), value.to_owned());
+            }
+        }
+    }
+    strings
+}
+
+impl AppManifest {
+    pub fn load(path: &Path) -> Result<AppManifest, Error> {
+        AppManifest::parse(&Vdf::load(path)?, path)
+    }
+
+    /// `path` is where the acf was read from.
+    pub fn parse(acf: &Vdf, path: &Path) -> Result<AppManifest, Error> {
+        let entry = |key: &str| acf.string_entry(&["AppState", key]);
+        let number = |key: &str| acf.u64_entry(&["AppState", key]).unwrap_or(0);
+        let app_id = acf
+            .u32_entry(&["AppState", "appid"])
+            .ok_or_else(|| anyhow!("No appid in {}", path.display()))?;
+        let mut installed_depots = Vec::new();
+        if let Some(depots) = acf.map_entry(&["AppState", "InstalledDepots"]) {
+            for (depot_id, depot) in depots {
+                if let (Ok(depot_id), Property::Map(depot)) = (u32::from_str(depot_id), depot) {
+                    let depot = string_map(Some(depot));
+                    let number = |key: &str| depot.get(key).and_then(|v| u64::from_str(v).ok());
+                    installed_depots.push(InstalledDepot {
+                        depot_id,
+                        manifest: number("manifest").unwrap_or(0),
+                        size: number("size").unwrap_or(0),
+                        dlc_app_id: number("dlcappid").map(|id| id as u32),
+                    });
+                }
+            }
+        }
+        installed_depots.sort_unstable_by_key(|d| d.depot_id);
+        Ok(AppManifest {
+            app_id,
+            name: entry("name"),
+            install_dir: entry("installdir").unwrap_or_default(),
+            size_on_disk: number("SizeOnDisk"),
+            build_id: number("buildid") as u32,
+            state_flags: number("StateFlags") as u32,
+            last_updated: number("LastUpdated") as u32,
+            last_owner: acf
+                .u64_entry(&["AppState", "LastOwner"])
+                .filter(|owner| *owner != 0),
+            bytes_to_download: number("BytesToDownload"),
+            bytes_downloaded: number("BytesDownloaded"),
+            bytes_to_stage: number("BytesToStage"),
+            bytes_staged: number("BytesStaged"),
+            auto_update_behavior: AutoUpdateBehavior::from(number("AutoUpdateBehavior") as u32),
+            installed_depots,
+            user_config: string_map(acf.map_entry(&["AppState", "UserConfig"])),
+            mounted_config: string_map(acf.map_entry(&["AppState", "MountedConfig"])),
+            path: path.to_path_buf(),
+            library: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        })
+    }
+
+    /// Look for the app's manifest in each of the given steamapps folders.
+    pub fn find(lib_folders: &[PathBuf], app_id: u32) -> Option<AppManifest> {
+        lib_folders
+            .iter()
+            .map(|folder| folder.join(format!("appmanifest_{}.acf", app_id)))
+            .filter(|path| path.exists())
+            .find_map(|path| match AppManifest::load(&path) {
+                Ok(manifest) => Some(manifest),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    None
+                }
+            })
+    }
+
+    pub fn state(&self) -> AppState {
+        AppState::from_bits_retain(self.state_flags)
+    }
+
+    /// Whether Steam has an update or download queued or running for the app.
+    pub fn download_pending(&self) -> bool {
+        let updating = AppState::UPDATE_REQUIRED
+            | AppState::UPDATE_RUNNING
+            | AppState::UPDATE_PAUSED
+            | AppState::UPDATE_STARTED
+            | AppState::DOWNLOADING
+            | AppState::STAGING;
+        self.state().intersects(updating) || self.bytes_to_download > self.bytes_downloaded
+    }
+
+    pub fn install_path(&self) -> PathBuf {
+        self.library.join("common").join(&self.install_dir)
+    }
+
+    /// The branch the app is installed from, "public" unless a beta is
+    /// selected.
+    pub fn branch(&self) -> &str {
+        match self.user_config.get("betakey") {
+            Some(branch) if !branch.is_empty() => branch,
+            _ => "public",
+        }
+    }
+}
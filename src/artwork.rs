@@ -0,0 +1,82 @@
+// Library artwork for an app. Steam caches the store images in
+// appcache/librarycache, either flat:
+//   <id>_library_600x900.jpg, <id>_library_600x900_2x.jpg, <id>_header.jpg,
+//   <id>_library_hero.jpg, <id>_logo.png
+// or, with newer clients, in a per app directory (possibly one level deeper
+// in a hash named directory):
+//   <id>/library_600x900.jpg, <id>/<hash>/library_hero.jpg, ...
+// Custom art set by the user lives in userdata/<account id>/config/grid:
+//   <id>p.png (capsule), <id>.png (header), <id>_hero.png, <id>_logo.png
+// and takes precedence over the cached images.
+
+use crate::steam_dir::steam_dir;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const GRID_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp"];
+
+#[derive(Debug, Default, Serialize)]
+pub struct Artwork {
+    pub capsule: Option<String>,
+    pub capsule_2x: Option<String>,
+    pub header: Option<String>,
+    pub hero: Option<String>,
+    pub logo: Option<String>,
+}
+
+fn to_string(path: PathBuf) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+// internal helper
+fn grid_override(grid: Option<&Path>, name: &str) -> Option<String> {
+    let grid = grid?;
+    GRID_EXTENSIONS
+        .iter()
+        .map(|ext| grid.join(format!("{}.{}", name, ext)))
+        .find(|path| path.exists())
+        .map(to_string)
+}
+
+// internal helper
+fn cached(library_cache: &Path, app_id: u32, file: &str) -> Option<String> {
+    let flat = library_cache.join(format!("{}_{}", app_id, file));
+    if flat.exists() {
+        return Some(to_string(flat));
+    }
+    let app_dir = library_cache.join(app_id.to_string());
+    let nested = app_dir.join(file);
+    if nested.exists() {
+        return Some(to_string(nested));
+    }
+    fs::read_dir(&app_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path().join(file))
+        .find(|path| path.exists())
+        .map(to_string)
+}
+
+impl Artwork {
+    /// `grid` is the config/grid directory of the logged in user, if known.
+    pub fn find(app_id: u32, grid: Option<&Path>) -> Artwork {
+        let library_cache = steam_dir().join("appcache/librarycache");
+        let custom_capsule = grid_override(grid, &format!("{}p", app_id));
+        let capsule = cached(&library_cache, app_id, "library_600x900.jpg");
+        Artwork {
+            capsule: custom_capsule.clone().or_else(|| capsule.clone()),
+            // There is no separate 2x override, the custom capsule is used
+            // for both.
+            capsule_2x: custom_capsule
+                .or_else(|| cached(&library_cache, app_id, "library_600x900_2x.jpg"))
+                .or(capsule),
+            header: grid_override(grid, &app_id.to_string())
+                .or_else(|| cached(&library_cache, app_id, "header.jpg")),
+            hero: grid_override(grid, &format!("{}_hero", app_id))
+                .or_else(|| cached(&library_cache, app_id, "library_hero.jpg")),
+            logo: grid_override(grid, &format!("{}_logo", app_id))
+                .or_else(|| cached(&library_cache, app_id, "logo.png")),
+        }
+    }
+}
@@ -1,14 +1,76 @@
 extern crate steam;
 
-use anyhow::Error;
-use clap::{App, Arg};
+use anyhow::{anyhow, Error};
+use clap::{App, Arg, SubCommand};
 use serde_json;
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::fs;
 use std::io::Read;
 use std::path::PathBuf;
 use std::str::FromStr;
-use steam::{app_info::AppInfo, package_info::PackageInfo, steam_game::SteamGame};
+use steam::{
+    app_info::AppInfo,
+    app_manifest::AppManifest,
+    controller::ControllerSupport,
+    deck::DeckCategory,
+    depot_manifest::DepotManifest,
+    icon,
+    library_folder::{LibraryFolder, MarkerStatus},
+    move_app, orphans,
+    ownership::Ownership,
+    package_info::PackageInfo,
+    platform::Platform,
+    steam_game::{DepotStatus, Dlc, SteamGame},
+    update_status::UpdateStatus,
+    verify, which,
+};
+
+fn format_size(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, units[unit])
+}
+
+fn libraries() -> Result<(), Error> {
+    for folder in LibraryFolder::load()? {
+        println!(
+            "{} {}",
+            folder.path.display(),
+            folder.label.as_deref().unwrap_or("")
+        );
+        match folder.manifests() {
+            Ok(manifests) => {
+                let size_on_disk: u64 = manifests.iter().map(|m| m.size_on_disk).sum();
+                println!(
+                    "\tapps: {} size on disk: {}",
+                    manifests.len(),
+                    format_size(size_on_disk)
+                );
+            }
+            Err(e) => println!("\t{}", e),
+        }
+        match (folder.free_space(), folder.total_space()) {
+            (Ok(free), Ok(total)) => {
+                println!("\tfree: {} of {}", format_size(free), format_size(total))
+            }
+            (Err(e), _) | (_, Err(e)) => println!("\t{}", e),
+        }
+        match folder.check_marker() {
+            MarkerStatus::NotRequired | MarkerStatus::Consistent => {}
+            MarkerStatus::Missing => println!("\tlibraryfolder.vdf is missing"),
+            MarkerStatus::Inconsistent(reason) => {
+                println!("\tlibraryfolder.vdf is inconsistent: {}", reason)
+            }
+        }
+    }
+    Ok(())
+}
 
 fn main() -> Result<(), Error> {
     let matches = App::new("steam")
@@ -60,6 +122,14 @@ fn main() -> Result<(), Error> {
                 .multiple(true)
                 .help("Dump app metadata"),
         )
+        .arg(
+            Arg::with_name("branches")
+                .long("branches")
+                .short("b")
+                .takes_value(true)
+                .multiple(true)
+                .help("List the branches (betas) of the given apps"),
+        )
         .arg(
             Arg::with_name("dump-pkg")
                 .long("dump-pkg")
@@ -71,7 +141,6 @@ fn main() -> Result<(), Error> {
         .arg(
             Arg::with_name("prop")
                 .long("prop")
-                .short("p")
                 .takes_value(true)
                 .help("Retrieve the specified property"),
         )
@@ -88,8 +157,183 @@ fn main() -> Result<(), Error> {
                 .takes_value(true)
                 .help("Only show installed or uninstalled games"),
         )
+        .arg(
+            Arg::with_name("ownership")
+                .long("ownership")
+                .short("o")
+                .takes_value(true)
+                .use_delimiter(true)
+                .help("Only show games with the given ownership (owned, free-to-play, family-shared, free-weekend, unknown). Unknown is left out by default"),
+        )
+        .arg(
+            Arg::with_name("dlc")
+                .long("dlc")
+                .takes_value(true)
+                .multiple(true)
+                .help("List the DLC of the given games"),
+        )
+        .arg(
+            Arg::with_name("deck")
+                .long("deck")
+                .takes_value(true)
+                .use_delimiter(true)
+                .help("Only show games with the given Steam Deck compatibility (verified, playable, unsupported, unknown)"),
+        )
+        .arg(
+            Arg::with_name("sort")
+                .long("sort")
+                .takes_value(true)
+                .possible_values(&["title", "developer", "release", "review"])
+                .default_value("title")
+                .help("Order of the listed games"),
+        )
+        .arg(
+            Arg::with_name("language")
+                .long("language")
+                .takes_value(true)
+                .help("Preferred language for game titles (e.g. japanese)"),
+        )
+        .arg(
+            Arg::with_name("audio")
+                .long("audio")
+                .takes_value(true)
+                .help("Only show games with full audio in the given language"),
+        )
+        .arg(
+            Arg::with_name("platform")
+                .long("platform")
+                .takes_value(true)
+                .use_delimiter(true)
+                .help("Only show games with native builds for the given platforms (windows, macos, linux)"),
+        )
+        .arg(
+            Arg::with_name("controller")
+                .long("controller")
+                .takes_value(true)
+                .use_delimiter(true)
+                .help("Only show games with the given controller support (full, partial, none)"),
+        )
+        .arg(
+            Arg::with_name("vr")
+                .long("vr")
+                .help("Only show games with VR support"),
+        )
+        .arg(
+            Arg::with_name("export-icons")
+                .long("export-icons")
+                .takes_value(true)
+                .help("Write the icons of the listed games as <appid>_<size>.png into the given directory"),
+        )
+        .arg(
+            Arg::with_name("icon-size")
+                .long("icon-size")
+                .takes_value(true)
+                .default_value("32")
+                .help("Size in pixels of exported icons"),
+        )
+        .subcommand(
+            SubCommand::with_name("libraries")
+                .about("Show each library folder with its size and free space"),
+        )
+        .subcommand(
+            SubCommand::with_name("outdated")
+                .about("List installed games that need an update"),
+        )
+        .subcommand(
+            SubCommand::with_name("depots")
+                .about("Report stale depots and owned DLC that is not installed")
+                .arg(
+                    Arg::with_name("appid")
+                        .multiple(true)
+                        .help("Only report on the given games"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("files")
+                .about("List the files of an installed game from its depot manifests")
+                .arg(Arg::with_name("appid").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Check an installed game's files against its depot manifests")
+                .arg(Arg::with_name("appid").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("which")
+                .about("Show which app a file or directory belongs to")
+                .arg(Arg::with_name("path").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("orphans")
+                .about("Find install, download and temp data no installed app refers to")
+                .arg(
+                    Arg::with_name("delete")
+                        .long("delete")
+                        .help("Delete what was found instead of only listing it"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("move")
+                .about("Move an installed game to another library folder")
+                .arg(Arg::with_name("appid").required(true))
+                .arg(
+                    Arg::with_name("library")
+                        .required(true)
+                        .help("Path or label of the target library"),
+                ),
+        )
         .get_matches();
 
+    if matches.subcommand_matches("libraries").is_some() {
+        return libraries();
+    }
+
+    if let Some(orphans) = matches.subcommand_matches("orphans") {
+        let delete = orphans.is_present("delete");
+        if delete && move_app::steam_running()? {
+            return Err(anyhow!("Steam is running, exit it before deleting orphans"));
+        }
+        let mut total = 0;
+        for orphan in orphans::find(&LibraryFolder::load()?)? {
+            total += orphan.size;
+            println!(
+                "{}{:?} {} {}",
+                if delete { "" } else { "would delete " },
+                orphan.kind,
+                orphan.path.display(),
+                format_size(orphan.size)
+            );
+            if delete {
+                orphan.delete()?;
+            }
+        }
+        println!("total {}", format_size(total));
+        return Ok(());
+    }
+
+    if let Some(move_matches) = matches.subcommand_matches("move") {
+        let id = u32::from_str(move_matches.value_of("appid").unwrap())?;
+        let library = move_matches.value_of("library").unwrap();
+        let folders = LibraryFolder::load()?;
+        let lib_folders: Vec<PathBuf> = folders.iter().map(LibraryFolder::steamapps).collect();
+        let manifest = match AppManifest::find(&lib_folders, id) {
+            Some(manifest) => manifest,
+            None => return Err(anyhow!("{} is not installed", id)),
+        };
+        let target = match LibraryFolder::find(folders, library) {
+            Some(target) => target,
+            None => return Err(anyhow!("{} is not a library folder", library)),
+        };
+        move_app::move_app(&manifest, &target)?;
+        println!(
+            "Moved {} from {} to {}",
+            id,
+            manifest.library.display(),
+            target.steamapps().display()
+        );
+        return Ok(());
+    }
+
     let mut count = 0;
     let max = usize::from_str(matches.value_of("max").unwrap_or("1000"))
         .expect("Unable to parse 'max' parameter.");
@@ -99,31 +343,282 @@ fn main() -> Result<(), Error> {
     let app_infos = AppInfo::load()?;
     let pkg_infos = PackageInfo::load()?;
 
-    let mut games = SteamGame::from(&app_infos, &pkg_infos)?;
+    let mut games = SteamGame::from(&app_infos, &pkg_infos, matches.value_of("language"))?;
+    if matches.subcommand_matches("outdated").is_some() {
+        games.sort_unstable_by(|e1, e2| e1.title.cmp(&e2.title));
+        for game in &games {
+            match game.update_status {
+                Some(UpdateStatus::UpdateQueued { bytes_to_download }) => println!(
+                    "{} {} queued, {} to download",
+                    game.id,
+                    game.title,
+                    format_size(bytes_to_download)
+                ),
+                Some(UpdateStatus::UpdateAvailable {
+                    installed_build,
+                    latest_build,
+                    estimated_download,
+                }) => println!(
+                    "{} {} build {} -> {}, {} to download",
+                    game.id,
+                    game.title,
+                    installed_build,
+                    latest_build,
+                    estimated_download
+                        .map(format_size)
+                        .unwrap_or_else(|| "unknown".to_string())
+                ),
+                _ => {}
+            }
+        }
+        return Ok(());
+    }
+    if let Some(depots) = matches.subcommand_matches("depots") {
+        let ids = depots
+            .values_of("appid")
+            .map(|ids| ids.map(u32::from_str).collect::<Result<Vec<u32>, _>>())
+            .transpose()?;
+        games.sort_unstable_by(|e1, e2| e1.title.cmp(&e2.title));
+        for game in &games {
+            if !game.installed.is_installed() {
+                continue;
+            }
+            if let Some(ids) = &ids {
+                if !ids.contains(&game.id) {
+                    continue;
+                }
+            }
+            let stale: Vec<DepotStatus> = game
+                .installed_depots()
+                .into_iter()
+                .filter(|d| d.stale)
+                .collect();
+            let missing_dlc: Vec<&Dlc> = game
+                .dlc
+                .iter()
+                .filter(|d| d.owned && !d.installed)
+                .collect();
+            if stale.is_empty() && missing_dlc.is_empty() {
+                continue;
+            }
+            println!("{} {}", game.id, game.title);
+            for depot in stale {
+                println!(
+                    "\tstale depot {} {} manifest {} latest {}",
+                    depot.depot_id,
+                    depot.name.as_deref().unwrap_or(""),
+                    depot.installed_manifest,
+                    depot.latest_manifest.unwrap_or(0)
+                );
+            }
+            for dlc in missing_dlc {
+                println!(
+                    "\tDLC not installed {} {}",
+                    dlc.id,
+                    dlc.title.as_deref().unwrap_or("")
+                );
+            }
+        }
+        return Ok(());
+    }
+    if let Some(files) = matches.subcommand_matches("files") {
+        let id = u32::from_str(files.value_of("appid").unwrap())?;
+        let manifest = match games
+            .iter()
+            .find(|g| g.id == id)
+            .and_then(|g| g.manifest.as_ref())
+        {
+            Some(manifest) => manifest,
+            None => return Err(anyhow!("{} is not installed", id)),
+        };
+        let lib_folders: Vec<PathBuf> = LibraryFolder::load()?
+            .iter()
+            .map(LibraryFolder::steamapps)
+            .collect();
+        for depot in &manifest.installed_depots {
+            let path = match DepotManifest::find(&lib_folders, depot.depot_id, depot.manifest) {
+                Some(path) => path,
+                None => {
+                    eprintln!(
+                        "No manifest {} for depot {} in depotcache",
+                        depot.manifest, depot.depot_id
+                    );
+                    continue;
+                }
+            };
+            let depot_manifest = DepotManifest::load(&path)?;
+            println!(
+                "depot {} manifest {} {} files {}",
+                depot_manifest.depot_id,
+                depot_manifest.manifest_id,
+                depot_manifest.files.len(),
+                format_size(depot_manifest.total_size())
+            );
+            for file in &depot_manifest.files {
+                println!("\t{} {} {:#x}", file.name, file.size, file.flags.bits());
+            }
+        }
+        return Ok(());
+    }
+    if let Some(verify) = matches.subcommand_matches("verify") {
+        let id = u32::from_str(verify.value_of("appid").unwrap())?;
+        let manifest = match games
+            .iter()
+            .find(|g| g.id == id)
+            .and_then(|g| g.manifest.as_ref())
+        {
+            Some(manifest) => manifest,
+            None => return Err(anyhow!("{} is not installed", id)),
+        };
+        let lib_folders: Vec<PathBuf> = LibraryFolder::load()?
+            .iter()
+            .map(LibraryFolder::steamapps)
+            .collect();
+        let report = verify::verify(manifest, &lib_folders)?;
+        for depot_id in &report.missing_manifests {
+            println!("no manifest for depot {}", depot_id);
+        }
+        for path in &report.missing {
+            println!("missing {}", path.display());
+        }
+        for path in &report.modified {
+            println!("modified {}", path.display());
+        }
+        for path in &report.extra {
+            println!("extra {}", path.display());
+        }
+        println!("{} files verified", report.verified);
+        if !report.is_ok() {
+            return Err(anyhow!("{} failed verification", id));
+        }
+        return Ok(());
+    }
+    if let Some(which) = matches.subcommand_matches("which") {
+        let path = which.value_of("path").unwrap();
+        let path = std::fs::canonicalize(path).map_err(|e| anyhow!("{}: {}", path, e))?;
+        match which::which(&path, &LibraryFolder::load()?, &app_infos) {
+            Some((app_id, kind)) => {
+                let title = games
+                    .iter()
+                    .find(|g| g.id == app_id)
+                    .map(|g| g.title.as_str())
+                    .unwrap_or("");
+                println!("{} {} {:?}", app_id, title, kind);
+            }
+            None => return Err(anyhow!("{} does not belong to any app", path.display())),
+        }
+        return Ok(());
+    }
     if matches.is_present("list") {
         games.sort_unstable_by(|e1, e2| e1.title.cmp(&e2.title));
+        match matches.value_of("sort").unwrap_or("title") {
+            "developer" => games.sort_by(|e1, e2| e1.developers.cmp(&e2.developers)),
+            "release" => games.sort_by_key(|g| g.original_release_date.or(g.release_date)),
+            "review" => games.sort_by_key(|g| Reverse(g.review_percentage)),
+            _ => {}
+        }
         if let Some(installed) = matches.value_of("installed") {
             let installed = bool::from_str(installed)?;
             games = games
                 .into_iter()
-                .filter(|g| g.installed == installed)
+                .filter(|g| g.installed.is_installed() == installed)
                 .collect();
         }
+        // packageinfo.vdf also lists packages the account merely came
+        // across, games without a license only show when asked for.
+        match matches.values_of("ownership") {
+            Some(ownerships) => {
+                let ownerships = ownerships
+                    .map(Ownership::from_str)
+                    .collect::<Result<Vec<Ownership>, Error>>()?;
+                games.retain(|g| ownerships.contains(&g.ownership));
+            }
+            None => games.retain(|g| g.ownership != Ownership::Unknown),
+        }
+        if let Some(language) = matches.value_of("audio") {
+            games.retain(|g| g.has_audio_in(language));
+        }
+        if let Some(platforms) = matches.values_of("platform") {
+            let platforms = platforms
+                .map(Platform::from_str)
+                .collect::<Result<Vec<Platform>, Error>>()?;
+            games.retain(|g| platforms.iter().any(|p| g.runs_natively(*p)));
+        }
+        if let Some(supports) = matches.values_of("controller") {
+            let supports = supports
+                .map(ControllerSupport::from_str)
+                .collect::<Result<Vec<ControllerSupport>, Error>>()?;
+            games.retain(|g| supports.contains(&g.controller_support));
+        }
+        if matches.is_present("vr") {
+            games.retain(|g| g.vr.is_vr());
+        }
+        if let Some(categories) = matches.values_of("deck") {
+            let categories = categories
+                .map(DeckCategory::from_str)
+                .collect::<Result<Vec<DeckCategory>, Error>>()?;
+            games.retain(|g| categories.contains(&g.deck_category()));
+        }
+        if let Some(dir) = matches.value_of("export-icons") {
+            let size = u32::from_str(matches.value_of("icon-size").unwrap_or("32"))?;
+            fs::create_dir_all(dir)?;
+            for game in games.iter().take(max) {
+                if let Some(icon) = &game.icon {
+                    let png = PathBuf::from(dir).join(format!("{}_{}.png", game.id, size));
+                    if let Err(e) = icon::extract_png(&PathBuf::from(icon), size, &png) {
+                        eprintln!("{}", e);
+                    }
+                }
+            }
+        }
         if matches.is_present("json") {
             let games_to_export: Vec<&SteamGame> = games.iter().take(max).collect();
             println!("{}", serde_json::to_string(&games_to_export)?);
         } else {
             for game in games.iter().take(max) {
                 println!(
-                    "{} {} {:?} {}",
-                    game.id, game.title, game.logo, game.installed
+                    "{} {} {:?} {} {:?}{}",
+                    game.id,
+                    game.title,
+                    game.logo,
+                    game.installed,
+                    game.ownership,
+                    match Platform::current() {
+                        Platform::Linux if game.needs_proton() => " proton",
+                        Platform::Linux => " native",
+                        _ => "",
+                    }
                 );
             }
         }
     }
+    if let Some(ids) = matches.values_of("dlc") {
+        for id in ids {
+            let id = u32::from_str(id)?;
+            for game in games.iter().filter(|g| g.id == id) {
+                println!("{} {}", game.id, game.title);
+                for dlc in &game.dlc {
+                    println!(
+                        "\t{} {} owned: {} installed: {}",
+                        dlc.id,
+                        dlc.title.as_deref().unwrap_or("none"),
+                        dlc.owned,
+                        dlc.installed
+                    );
+                }
+            }
+        }
+    }
     if matches.is_present("list-pkgs") {
         for pkg_info in pkg_infos.iter().take(max) {
-            println!("{}", pkg_info.id);
+            println!(
+                "{} {:?} {:?} {:?} {:?}",
+                pkg_info.id,
+                pkg_info.billing_type(),
+                pkg_info.license_type(),
+                pkg_info.status(),
+                pkg_info.app_ids()
+            );
         }
     }
     let path: Option<Vec<&str>> = match matches.value_of("prop") {
@@ -137,7 +632,7 @@ fn main() -> Result<(), Error> {
             let id = u32::from_str(id)?;
             for app_info in &app_infos {
                 if app_info.u32_entry(&["appinfo", "appid"]).unwrap() == id {
-                    println!("State: {:#X}", app_info.state);
+                    println!("State: {:#X} ({})", app_info.state, app_info.info_state());
                     if path.is_some() {
                         app_info.print_entry(path.as_ref().unwrap());
                     } else {
@@ -148,6 +643,37 @@ fn main() -> Result<(), Error> {
         }
     }
 
+    if let Some(ids) = matches.values_of("branches") {
+        for id in ids {
+            let id = u32::from_str(id)?;
+            for app_info in &app_infos {
+                if app_info.u32_entry(&["appinfo", "appid"]).unwrap() == id {
+                    println!(
+                        "{} {}",
+                        id,
+                        app_info
+                            .string_entry(&["appinfo", "common", "name"])
+                            .unwrap_or("none".to_string())
+                    );
+                    for branch in app_info.branches() {
+                        println!(
+                            "\t{} {} {} {}{}",
+                            branch.name,
+                            branch.build_id,
+                            branch.time_updated.unwrap_or(0),
+                            if branch.pwd_required {
+                                "(password) "
+                            } else {
+                                ""
+                            },
+                            branch.description.unwrap_or_default()
+                        );
+                    }
+                }
+            }
+        }
+    }
+
     if let Some(ids) = matches.values_of("dump-pkg") {
         for id in ids {
             println!("{}", id);
@@ -0,0 +1,122 @@
+// Names for the numeric store category and genre ids found in appinfo's
+// `common/category` and `common/genres`. Ids missing from these tables are
+// kept but left unnamed.
+
+use serde::Serialize;
+
+const CATEGORIES: &[(u32, &str)] = &[
+    (1, "Multi-player"),
+    (2, "Single-player"),
+    (6, "Mods (require HL2)"),
+    (7, "Mods (require HL1)"),
+    (8, "Valve Anti-Cheat enabled"),
+    (9, "Co-op"),
+    (10, "Game demo"),
+    (12, "HDR available"),
+    (13, "Captions available"),
+    (14, "Commentary available"),
+    (15, "Stats"),
+    (16, "Includes Source SDK"),
+    (17, "Includes level editor"),
+    (18, "Partial Controller Support"),
+    (19, "Mods"),
+    (20, "MMO"),
+    (21, "Downloadable Content"),
+    (22, "Steam Achievements"),
+    (23, "Steam Cloud"),
+    (24, "Shared/Split Screen"),
+    (25, "Steam Leaderboards"),
+    (27, "Cross-Platform Multiplayer"),
+    (28, "Full controller support"),
+    (29, "Steam Trading Cards"),
+    (30, "Steam Workshop"),
+    (31, "VR Support"),
+    (32, "Steam Turn Notifications"),
+    (35, "In-App Purchases"),
+    (36, "Online PvP"),
+    (37, "Shared/Split Screen PvP"),
+    (38, "Online Co-op"),
+    (39, "Shared/Split Screen Co-op"),
+    (40, "SteamVR Collectibles"),
+    (41, "Remote Play on Phone"),
+    (42, "Remote Play on Tablet"),
+    (43, "Remote Play on TV"),
+    (44, "Remote Play Together"),
+    (47, "LAN PvP"),
+    (48, "LAN Co-op"),
+    (49, "PvP"),
+    (52, "Tracked Controller Support"),
+    (53, "VR Supported"),
+    (54, "VR Only"),
+    (61, "HDR available"),
+    (62, "Family Sharing"),
+];
+
+const GENRES: &[(u32, &str)] = &[
+    (1, "Action"),
+    (2, "Strategy"),
+    (3, "RPG"),
+    (4, "Casual"),
+    (9, "Racing"),
+    (18, "Sports"),
+    (23, "Indie"),
+    (25, "Adventure"),
+    (28, "Simulation"),
+    (29, "Massively Multiplayer"),
+    (37, "Free to Play"),
+    (50, "Accounting"),
+    (51, "Animation & Modeling"),
+    (52, "Audio Production"),
+    (53, "Design & Illustration"),
+    (54, "Education"),
+    (55, "Photo Editing"),
+    (56, "Software Training"),
+    (57, "Utilities"),
+    (58, "Video Production"),
+    (59, "Web Publishing"),
+    (60, "Game Development"),
+    (70, "Early Access"),
+    (71, "Sexual Content"),
+    (72, "Nudity"),
+    (73, "Violent"),
+    (74, "Gore"),
+    (81, "Documentary"),
+    (84, "Tutorial"),
+];
+
+fn lookup(table: &[(u32, &'static str)], id: u32) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(table_id, _)| *table_id == id)
+        .map(|(_, name)| *name)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Category {
+    pub id: u32,
+    pub name: Option<&'static str>,
+}
+
+impl Category {
+    pub fn from_id(id: u32) -> Category {
+        Category {
+            id,
+            name: lookup(CATEGORIES, id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Genre {
+    pub id: u32,
+    pub name: Option<&'static str>,
+}
+
+impl Genre {
+    pub fn from_id(id: u32) -> Genre {
+        Genre {
+            id,
+            name: lookup(GENRES, id),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+// Controller support from appinfo's `common/controller_support` and the
+// Steam Input defaults under `config`:
+//   "steamcontrollertemplateindex" "1"
+//   "steamcontrollerconfigdetails" { "<file id>" { "controller_type" "controller_ps4" ... } }
+
+use crate::app_info::{string_prop, u32_prop, Property};
+use anyhow::{anyhow, Error};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ControllerSupport {
+    None,
+    Partial,
+    Full,
+}
+
+impl FromStr for ControllerSupport {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ControllerSupport, Error> {
+        match s.to_lowercase().as_str() {
+            "none" | "" => Ok(ControllerSupport::None),
+            "partial" => Ok(ControllerSupport::Partial),
+            "full" => Ok(ControllerSupport::Full),
+            _ => Err(anyhow!("Unknown controller support: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ControllerConfig {
+    pub file_id: u64,
+    pub controller_type: Option<String>,
+    pub enabled_branches: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SteamInput {
+    pub template_index: Option<u32>,
+    pub touch_template_index: Option<u32>,
+    pub configs: Vec<ControllerConfig>,
+}
+
+impl SteamInput {
+    pub fn from_props(config: &HashMap<String, Property>) -> SteamInput {
+        let mut configs = Vec::new();
+        if let Some(Property::Map(details)) = config.get("steamcontrollerconfigdetails") {
+            for (file_id, detail) in details {
+                if let (Ok(file_id), Property::Map(detail)) = (u64::from_str(file_id), detail) {
+                    configs.push(ControllerConfig {
+                        file_id,
+                        controller_type: string_prop(detail, "controller_type"),
+                        enabled_branches: string_prop(detail, "enabled_branches"),
+                    });
+                }
+            }
+        }
+        configs.sort_unstable_by_key(|c| c.file_id);
+        SteamInput {
+            template_index: u32_prop(config, "steamcontrollertemplateindex"),
+            touch_template_index: u32_prop(config, "steamcontrollertouchtemplateindex"),
+            configs,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+// Steam Deck compatibility from appinfo's `common/steam_deck_compatibility`:
+//   "category" "3"
+//   "test_timestamp" "1650000000"
+//   "tested_build_id" "8400000"
+//   "tests" { "0" { "display" "4" "token" "#SteamDeckVerified_TestResult_..." } }
+
+use crate::app_info::{string_prop, u32_prop, Property};
+use anyhow::{anyhow, Error};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum DeckCategory {
+    Unknown,
+    Unsupported,
+    Playable,
+    Verified,
+}
+
+impl From<u32> for DeckCategory {
+    fn from(value: u32) -> DeckCategory {
+        match value {
+            1 => DeckCategory::Unsupported,
+            2 => DeckCategory::Playable,
+            3 => DeckCategory::Verified,
+            _ => DeckCategory::Unknown,
+        }
+    }
+}
+
+impl FromStr for DeckCategory {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<DeckCategory, Error> {
+        match s.to_lowercase().as_str() {
+            "unknown" => Ok(DeckCategory::Unknown),
+            "unsupported" => Ok(DeckCategory::Unsupported),
+            "playable" => Ok(DeckCategory::Playable),
+            "verified" => Ok(DeckCategory::Verified),
+            _ => Err(anyhow!("Unknown deck category: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeckTest {
+    pub display: u32,
+    pub token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeckCompatibility {
+    pub category: DeckCategory,
+    pub test_timestamp: Option<u32>,
+    pub tested_build_id: Option<u32>,
+    pub tests: Vec<DeckTest>,
+}
+
+impl DeckCompatibility {
+    pub fn from_props(props: &HashMap<String, Property>) -> DeckCompatibility {
+        let mut tests: Vec<(u32, DeckTest)> = Vec::new();
+        if let Some(Property::Map(entries)) = props.get("tests") {
+            for (key, test) in entries {
+                if let Property::Map(test) = test {
+                    tests.push((
+                        u32::from_str(key).unwrap_or(u32::MAX),
+                        DeckTest {
+                            display: u32_prop(test, "display").unwrap_or(0),
+                            token: string_prop(test, "token").unwrap_or_default(),
+                        },
+                    ));
+                }
+            }
+        }
+        tests.sort_unstable_by_key(|(index, _)| *index);
+        DeckCompatibility {
+            category: DeckCategory::from(u32_prop(props, "category").unwrap_or(0)),
+            test_timestamp: u32_prop(props, "test_timestamp"),
+            tested_build_id: u32_prop(props, "tested_build_id"),
+            tests: tests.into_iter().map(|(_, test)| test).collect(),
+        }
+    }
+}
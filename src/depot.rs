@@ -0,0 +1,137 @@
+// Typed view of the `depots` section of an app's appinfo entry.
+//
+// Each numbered child of `depots` describes one depot:
+//   "config" { "oslist" "windows,linux" "osarch" "64" "language" "german" }
+//   "manifests" { "public" { "gid" "..." "size" "..." "download" "..." } }
+//   "maxsize" "..."
+//   "dlcappid" "..."
+//   "depotfromapp" "..."
+// The `branches` child lists the app's branches (public plus any betas):
+//   "beta" { "buildid" "..." "description" "..." "pwdrequired" "1" "timeupdated" "..." }
+// Older appinfo files store the manifest gid directly as the branch value.
+// Numbers are usually stored as strings but occasionally as uint32.
+
+use crate::app_info::{bool_prop, string_prop, u32_prop, u64_prop, Property};
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct Manifest {
+    pub gid: u64,
+    pub size: Option<u64>,
+    pub download: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct Depot {
+    pub id: u32,
+    pub name: Option<String>,
+    pub os_list: Vec<String>,
+    pub os_arch: Option<String>,
+    pub language: Option<String>,
+    pub max_size: Option<u64>,
+    pub manifests: HashMap<String, Manifest>,
+    pub dlc_app_id: Option<u32>,
+    pub depot_from_app: Option<u32>,
+    pub shared_install: bool,
+}
+
+#[derive(Debug)]
+pub struct Branch {
+    pub name: String,
+    pub build_id: u32,
+    pub description: Option<String>,
+    pub pwd_required: bool,
+    pub time_updated: Option<u32>,
+}
+
+impl Branch {
+    pub fn from_props(name: &str, props: &HashMap<String, Property>) -> Branch {
+        Branch {
+            name: name.to_owned(),
+            build_id: u32_prop(props, "buildid").unwrap_or(0),
+            description: string_prop(props, "description"),
+            pwd_required: bool_prop(props, "pwdrequired"),
+            time_updated: u32_prop(props, "timeupdated"),
+        }
+    }
+}
+
+impl Depot {
+    pub fn from_props(id: u32, props: &HashMap<String, Property>) -> Depot {
+        let mut os_list = Vec::new();
+        let mut os_arch = None;
+        let mut language = None;
+        if let Some(Property::Map(config)) = props.get("config") {
+            if let Some(list) = string_prop(config, "oslist") {
+                os_list = list
+                    .split(',')
+                    .map(|os| os.trim().to_lowercase())
+                    .filter(|os| !os.is_empty())
+                    .collect();
+            }
+            os_arch = string_prop(config, "osarch");
+            language = string_prop(config, "language");
+        }
+        let mut manifests = HashMap::new();
+        if let Some(Property::Map(branches)) = props.get("manifests") {
+            for (branch, value) in branches {
+                let manifest = match value {
+                    Property::Map(manifest) => u64_prop(manifest, "gid").map(|gid| Manifest {
+                        gid,
+                        size: u64_prop(manifest, "size"),
+                        download: u64_prop(manifest, "download"),
+                    }),
+                    _ => u64_prop(branches, branch).map(|gid| Manifest {
+                        gid,
+                        size: None,
+                        download: None,
+                    }),
+                };
+                if let Some(manifest) = manifest {
+                    manifests.insert(branch.to_owned(), manifest);
+                }
+            }
+        }
+        Depot {
+            id,
+            name: string_prop(props, "name"),
+            os_list,
+            os_arch,
+            language,
+            max_size: u64_prop(props, "maxsize"),
+            manifests,
+            dlc_app_id: u32_prop(props, "dlcappid"),
+            depot_from_app: u32_prop(props, "depotfromapp"),
+            shared_install: bool_prop(props, "sharedinstall"),
+        }
+    }
+
+    /// Whether Steam would install this depot for the given os ("windows",
+    /// "macos", "linux"), architecture ("32", "64") and language.
+    pub fn applies_to(&self, os: &str, arch: &str, language: &str) -> bool {
+        if !self.os_list.is_empty() && !self.os_list.iter().any(|o| o.eq_ignore_ascii_case(os)) {
+            return false;
+        }
+        if let Some(os_arch) = &self.os_arch {
+            if os_arch != arch {
+                return false;
+            }
+        }
+        if let Some(depot_language) = &self.language {
+            if !depot_language.eq_ignore_ascii_case(language) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Size of the depot's content on the given branch. Falls back to the
+    /// public branch and then to `maxsize` when no manifest size is known.
+    pub fn size(&self, branch: &str) -> Option<u64> {
+        self.manifests
+            .get(branch)
+            .or_else(|| self.manifests.get("public"))
+            .and_then(|manifest| manifest.size)
+            .or(self.max_size)
+    }
+}
@@ -0,0 +1,419 @@
+// Parser for the binary depot manifests Steam keeps in depotcache as
+// <depot id>_<manifest id>.manifest.
+//
+// The file is a series of sections, each a little endian u32 magic followed
+// by a u32 length and that many bytes, until the end of manifest magic:
+//   payload   - ContentManifestPayload protobuf, every file and its chunks
+//   metadata  - ContentManifestMetadata protobuf, depot id, manifest id, sizes
+//   signature - ContentManifestSignature protobuf, not used here
+//
+// Message layouts follow SteamKit's content_manifest.proto:
+//   ContentManifestPayload { repeated FileMapping mappings = 1; }
+//   FileMapping { string filename = 1; uint64 size = 2; uint32 flags = 3;
+//                 bytes sha_filename = 4; bytes sha_content = 5;
+//                 repeated ChunkData chunks = 6; string linktarget = 7; }
+//   ChunkData { bytes sha = 1; fixed32 crc = 2; uint64 offset = 3;
+//               uint32 cb_original = 4; uint32 cb_compressed = 5; }
+//   ContentManifestMetadata { uint32 depot_id = 1; uint64 gid_manifest = 2;
+//                 uint32 creation_time = 3; bool filenames_encrypted = 4;
+//                 uint64 cb_disk_original = 5; uint64 cb_disk_compressed = 6;
+//                 uint32 unique_chunks = 7; }
+// https://github.com/SteamRE/SteamKit/blob/master/Resources/Protobufs/steam/content_manifest.proto
+
+use crate::steam_dir::steam_dir;
+use anyhow::{anyhow, Error};
+use bitflags::bitflags;
+use serde::Serialize;
+use std::convert::{TryFrom, TryInto};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const PAYLOAD_MAGIC: u32 = 0x71F6_17D0;
+const METADATA_MAGIC: u32 = 0x1F48_12BE;
+const SIGNATURE_MAGIC: u32 = 0x1B81_B817;
+const END_OF_MANIFEST_MAGIC: u32 = 0x32C4_15AB;
+// Manifests from before the protobuf format.
+const OLD_MANIFEST_MAGIC: u32 = 0x1634_9781;
+
+bitflags! {
+    // EDepotFileFlag
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct DepotFileFlags: u32 {
+        const USER_CONFIG = 0x1;
+        const VERSIONED_USER_CONFIG = 0x2;
+        const ENCRYPTED = 0x4;
+        const READ_ONLY = 0x8;
+        const HIDDEN = 0x10;
+        const EXECUTABLE = 0x20;
+        const DIRECTORY = 0x40;
+        const CUSTOM_EXECUTABLE = 0x80;
+        const INSTALL_SCRIPT = 0x100;
+        const SYMLINK = 0x200;
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Chunk {
+    pub sha: Vec<u8>,
+    pub crc: u32,
+    pub offset: u64,
+    pub original_size: u32,
+    pub compressed_size: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileEntry {
+    pub name: String,
+    pub size: u64,
+    pub flags: DepotFileFlags,
+    pub sha_filename: Vec<u8>,
+    pub sha_content: Vec<u8>,
+    pub chunks: Vec<Chunk>,
+    pub link_target: Option<String>,
+}
+
+impl FileEntry {
+    /// The file's path relative to the install directory. Manifests use
+    /// Windows separators.
+    pub fn path(&self) -> PathBuf {
+        self.name.split('\\').collect()
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.flags.contains(DepotFileFlags::DIRECTORY)
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DepotManifest {
+    pub depot_id: u32,
+    pub manifest_id: u64,
+    pub creation_time: u32,
+    pub filenames_encrypted: bool,
+    pub original_size: u64,
+    pub compressed_size: u64,
+    pub unique_chunks: u32,
+    pub files: Vec<FileEntry>,
+}
+
+fn le_u32(buf: &[u8], pos: &mut usize) -> Result<u32, Error> {
+    let bytes = buf
+        .get(*pos..*pos + 4)
+        .ok_or_else(|| anyhow!("Unexpected end of manifest at {}", *pos))?;
+    *pos += 4;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+// A minimal protobuf reader, enough for the manifest messages.
+struct Fields<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+enum Value<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl<'a> Value<'a> {
+    fn as_u64(&self) -> u64 {
+        match self {
+            Value::Varint(value) | Value::Fixed64(value) => *value,
+            Value::Fixed32(value) => *value as u64,
+            Value::Bytes(_) => 0,
+        }
+    }
+
+    fn as_bytes(&self) -> &'a [u8] {
+        match self {
+            Value::Bytes(bytes) => bytes,
+            _ => &[],
+        }
+    }
+
+    fn as_string(&self) -> String {
+        String::from_utf8_lossy(self.as_bytes())
+            .trim_end_matches('\0')
+            .to_string()
+    }
+}
+
+impl<'a> Fields<'a> {
+    fn new(buf: &'a [u8]) -> Fields<'a> {
+        Fields { buf, pos: 0 }
+    }
+
+    fn varint(&mut self) -> Result<u64, Error> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .buf
+                .get(self.pos)
+                .ok_or_else(|| anyhow!("Truncated varint"))?;
+            self.pos += 1;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(anyhow!("Varint too long"))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .ok_or_else(|| anyhow!("Invalid field length: {}", len))?;
+        let bytes = self
+            .buf
+            .get(self.pos..end)
+            .ok_or_else(|| anyhow!("Truncated field"))?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn next_field(&mut self) -> Result<Option<(u64, Value<'a>)>, Error> {
+        if self.pos >= self.buf.len() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let value = match key & 0x7 {
+            0 => Value::Varint(self.varint()?),
+            1 => Value::Fixed64(u64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+            2 => {
+                let len =
+                    usize::try_from(self.varint()?).map_err(|_| anyhow!("Invalid field length"))?;
+                Value::Bytes(self.take(len)?)
+            }
+            5 => Value::Fixed32(u32::from_le_bytes(self.take(4)?.try_into().unwrap())),
+            wire_type => return Err(anyhow!("Unsupported wire type: {}", wire_type)),
+        };
+        Ok(Some((key >> 3, value)))
+    }
+}
+
+fn parse_chunk(buf: &[u8]) -> Result<Chunk, Error> {
+    let mut chunk = Chunk {
+        sha: Vec::new(),
+        crc: 0,
+        offset: 0,
+        original_size: 0,
+        compressed_size: 0,
+    };
+    let mut fields = Fields::new(buf);
+    while let Some((field, value)) = fields.next_field()? {
+        match field {
+            1 => chunk.sha = value.as_bytes().to_vec(),
+            2 => chunk.crc = value.as_u64() as u32,
+            3 => chunk.offset = value.as_u64(),
+            4 => chunk.original_size = value.as_u64() as u32,
+            5 => chunk.compressed_size = value.as_u64() as u32,
+            _ => {}
+        }
+    }
+    Ok(chunk)
+}
+
+fn parse_file(buf: &[u8]) -> Result<FileEntry, Error> {
+    let mut file = FileEntry {
+        name: String::new(),
+        size: 0,
+        flags: DepotFileFlags::empty(),
+        sha_filename: Vec::new(),
+        sha_content: Vec::new(),
+        chunks: Vec::new(),
+        link_target: None,
+    };
+    let mut fields = Fields::new(buf);
+    while let Some((field, value)) = fields.next_field()? {
+        match field {
+            1 => file.name = value.as_string(),
+            2 => file.size = value.as_u64(),
+            3 => file.flags = DepotFileFlags::from_bits_retain(value.as_u64() as u32),
+            4 => file.sha_filename = value.as_bytes().to_vec(),
+            5 => file.sha_content = value.as_bytes().to_vec(),
+            6 => file.chunks.push(parse_chunk(value.as_bytes())?),
+            7 => file.link_target = Some(value.as_string()).filter(|t| !t.is_empty()),
+            _ => {}
+        }
+    }
+    file.chunks.sort_unstable_by_key(|c| c.offset);
+    Ok(file)
+}
+
+impl DepotManifest {
+    pub fn load(path: &Path) -> Result<DepotManifest, Error> {
+        let buf =
+            fs::read(path).map_err(|e| anyhow!("Unable to read {}: {}", path.display(), e))?;
+        DepotManifest::parse(&buf).map_err(|e| anyhow!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(buf: &[u8]) -> Result<DepotManifest, Error> {
+        let mut manifest = DepotManifest::default();
+        let mut pos = 0;
+        loop {
+            let magic = le_u32(buf, &mut pos)?;
+            match magic {
+                END_OF_MANIFEST_MAGIC => break,
+                OLD_MANIFEST_MAGIC => {
+                    return Err(anyhow!("Pre-protobuf depot manifests are not supported"))
+                }
+                PAYLOAD_MAGIC | METADATA_MAGIC | SIGNATURE_MAGIC => {}
+                _ => return Err(anyhow!("Unknown section magic: 0x{:x}", magic)),
+            }
+            let len = le_u32(buf, &mut pos)? as usize;
+            let section = buf
+                .get(pos..pos + len)
+                .ok_or_else(|| anyhow!("Section 0x{:x} is truncated", magic))?;
+            pos += len;
+            let mut fields = Fields::new(section);
+            match magic {
+                PAYLOAD_MAGIC => {
+                    while let Some((field, value)) = fields.next_field()? {
+                        if field == 1 {
+                            manifest.files.push(parse_file(value.as_bytes())?);
+                        }
+                    }
+                }
+                METADATA_MAGIC => {
+                    while let Some((field, value)) = fields.next_field()? {
+                        match field {
+                            1 => manifest.depot_id = value.as_u64() as u32,
+                            2 => manifest.manifest_id = value.as_u64(),
+                            3 => manifest.creation_time = value.as_u64() as u32,
+                            4 => manifest.filenames_encrypted = value.as_u64() != 0,
+                            5 => manifest.original_size = value.as_u64(),
+                            6 => manifest.compressed_size = value.as_u64(),
+                            7 => manifest.unique_chunks = value.as_u64() as u32,
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+            // Some manifests end without the end of manifest magic.
+            if pos >= buf.len() {
+                break;
+            }
+        }
+        manifest
+            .files
+            .sort_unstable_by(|f1, f2| f1.name.cmp(&f2.name));
+        Ok(manifest)
+    }
+
+    /// Look for <depot>_<manifest>.manifest in the Steam directory's
+    /// depotcache and in the depotcache of each of the given steamapps
+    /// folders.
+    pub fn find(lib_folders: &[PathBuf], depot_id: u32, manifest_id: u64) -> Option<PathBuf> {
+        let name = format!("{}_{}.manifest", depot_id, manifest_id);
+        std::iter::once(steam_dir().join("depotcache"))
+            .chain(lib_folders.iter().map(|folder| folder.join("depotcache")))
+            .map(|dir| dir.join(&name))
+            .find(|path| path.exists())
+    }
+
+    /// Total size of the files in the depot.
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn uint(field: u64, value: u64, out: &mut Vec<u8>) {
+        varint(field << 3, out);
+        varint(value, out);
+    }
+
+    fn bytes(field: u64, value: &[u8], out: &mut Vec<u8>) {
+        varint(field << 3 | 2, out);
+        varint(value.len() as u64, out);
+        out.extend_from_slice(value);
+    }
+
+    fn section(magic: u32, body: &[u8], out: &mut Vec<u8>) {
+        out.extend_from_slice(&magic.to_le_bytes());
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(body);
+    }
+
+    fn manifest() -> Vec<u8> {
+        let mut chunk = Vec::new();
+        bytes(1, &[0xAB; 20], &mut chunk);
+        chunk.push(2 << 3 | 5);
+        chunk.extend_from_slice(&0xDEAD_BEEFu32.to_le_bytes());
+        uint(3, 0, &mut chunk);
+        uint(4, 10, &mut chunk);
+        uint(5, 8, &mut chunk);
+        let mut file = Vec::new();
+        bytes(1, b"bin\\game.exe", &mut file);
+        uint(2, 10, &mut file);
+        uint(3, 0x20, &mut file);
+        bytes(6, &chunk, &mut file);
+        let mut dir = Vec::new();
+        bytes(1, b"bin", &mut dir);
+        uint(3, 0x40, &mut dir);
+        let mut payload = Vec::new();
+        bytes(1, &file, &mut payload);
+        bytes(1, &dir, &mut payload);
+        let mut metadata = Vec::new();
+        uint(1, 229001, &mut metadata);
+        uint(2, 1234567890123, &mut metadata);
+        uint(5, 10, &mut metadata);
+        uint(7, 1, &mut metadata);
+
+        let mut buf = Vec::new();
+        section(PAYLOAD_MAGIC, &payload, &mut buf);
+        section(METADATA_MAGIC, &metadata, &mut buf);
+        section(SIGNATURE_MAGIC, &[], &mut buf);
+        buf.extend_from_slice(&END_OF_MANIFEST_MAGIC.to_le_bytes());
+        buf
+    }
+
+    #[test]
+    fn parses_payload_and_metadata() {
+        let manifest = DepotManifest::parse(&manifest()).unwrap();
+        assert_eq!(manifest.depot_id, 229001);
+        assert_eq!(manifest.manifest_id, 1234567890123);
+        assert_eq!(manifest.original_size, 10);
+        assert_eq!(manifest.unique_chunks, 1);
+        assert_eq!(manifest.files.len(), 2);
+        let dir = &manifest.files[0];
+        assert!(dir.is_directory());
+        let file = &manifest.files[1];
+        assert_eq!(file.path(), Path::new("bin").join("game.exe"));
+        assert_eq!(file.size, 10);
+        assert_eq!(file.flags, DepotFileFlags::EXECUTABLE);
+        assert_eq!(file.chunks.len(), 1);
+        assert_eq!(file.chunks[0].crc, 0xDEAD_BEEF);
+        assert_eq!(file.chunks[0].original_size, 10);
+        assert_eq!(file.chunks[0].compressed_size, 8);
+        assert_eq!(manifest.total_size(), 10);
+    }
+
+    #[test]
+    fn rejects_corrupt_lengths() {
+        let mut payload = Vec::new();
+        varint(1 << 3 | 2, &mut payload);
+        varint(u64::MAX, &mut payload);
+        let mut buf = Vec::new();
+        section(PAYLOAD_MAGIC, &payload, &mut buf);
+        assert!(DepotManifest::parse(&buf).is_err());
+
+        let buf = manifest();
+        assert!(DepotManifest::parse(&buf[..buf.len() / 2]).is_err());
+    }
+}
@@ -0,0 +1,30 @@
+// Client icons. Appinfo's `common/clienticon` holds a hash and the icon is
+// stored as steam/games/<hash>.ico in the Steam directory.
+
+use crate::steam_dir::steam_dir;
+use anyhow::{anyhow, Error};
+use image::imageops::FilterType;
+use image::ImageFormat;
+use std::path::{Path, PathBuf};
+
+pub fn icon_path(hash: &str) -> PathBuf {
+    steam_dir()
+        .join("steam/games")
+        .join(format!("{}.ico", hash))
+}
+
+/// Decode an .ico file and write it as a `size`x`size` PNG. The largest
+/// image in the icon is used as the source.
+pub fn extract_png(ico: &Path, size: u32, png: &Path) -> Result<(), Error> {
+    let image =
+        image::open(ico).map_err(|e| anyhow!("Unable to decode {}: {}", ico.display(), e))?;
+    let image = if image.width() == size && image.height() == size {
+        image
+    } else {
+        image.resize_exact(size, size, FilterType::Lanczos3)
+    };
+    image
+        .save_with_format(png, ImageFormat::Png)
+        .map_err(|e| anyhow!("Unable to write {}: {}", png.display(), e))?;
+    Ok(())
+}
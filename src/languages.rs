@@ -0,0 +1,36 @@
+// Supported languages from appinfo's `common/supported_languages`:
+//   "japanese" { "supported" "true" "full_audio" "true" "subtitles" "true" }
+// Older entries only have `common/languages`:
+//   "japanese" "1"
+
+use crate::app_info::{bool_prop, Property};
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Language {
+    pub name: String,
+    pub supported: bool,
+    pub full_audio: bool,
+    pub subtitles: bool,
+}
+
+impl Language {
+    pub fn from_props(name: &str, props: &HashMap<String, Property>) -> Language {
+        Language {
+            name: name.to_owned(),
+            supported: bool_prop(props, "supported"),
+            full_audio: bool_prop(props, "full_audio"),
+            subtitles: bool_prop(props, "subtitles"),
+        }
+    }
+
+    pub fn from_legacy(name: &str, props: &HashMap<String, Property>) -> Language {
+        Language {
+            name: name.to_owned(),
+            supported: bool_prop(props, name),
+            full_audio: false,
+            subtitles: false,
+        }
+    }
+}
@@ -1,3 +1,24 @@
 pub mod app_info;
+pub mod app_manifest;
+pub mod artwork;
+pub mod categories;
+pub mod controller;
+pub mod deck;
+pub mod depot;
+pub mod depot_manifest;
+pub mod icon;
+pub mod languages;
+pub mod library_folder;
+pub mod move_app;
+pub mod orphans;
+pub mod ownership;
 pub mod package_info;
+pub mod platform;
+pub mod state;
+pub mod steam_dir;
 pub mod steam_game;
+pub mod update_status;
+pub mod vdf;
+pub mod verify;
+pub mod vr;
+pub mod which;
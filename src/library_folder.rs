@@ -0,0 +1,372 @@
+// Steam library folders from steamapps/libraryfolders.vdf.
+//
+// Older clients only list the extra libraries, the Steam directory itself
+// is implied:
+//   "LibraryFolders" { "TimeNextStatsReport" "..." "1" "D:\\Games" }
+// Current clients list every library, including the Steam directory, and
+// leave a libraryfolder.vdf marker in the root of each additional library:
+//   "libraryfolder" { "contentid" "..." "label" "" }
+// The listing in libraryfolders.vdf looks like:
+//   "libraryfolders"
+//   {
+//       "0"
+//       {
+//           "path"      "C:\\Program Files (x86)\\Steam"
+//           "label"     ""
+//           "contentid" "..."
+//           "totalsize" "0"
+//           "apps" { "220" "4187000000" }
+//       }
+//   }
+
+use crate::app_manifest::AppManifest;
+use crate::steam_dir::steam_dir;
+use crate::vdf::{Property, Vdf};
+use anyhow::{anyhow, Error};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Debug, Serialize)]
+pub struct LibraryFolder {
+    pub path: PathBuf,
+    pub label: Option<String>,
+    pub content_id: Option<u64>,
+    pub total_size: Option<u64>,
+    // App id to size on disk. Only filled in by the current format.
+    pub apps: HashMap<u32, u64>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub enum MarkerStatus {
+    // The Steam directory itself has no marker.
+    NotRequired,
+    Missing,
+    Consistent,
+    Inconsistent(String),
+}
+
+// Library paths are compared canonicalized, so a symlinked Steam directory
+// (~/.steam/steam) matches the real path libraryfolders.vdf lists. Only
+// Windows paths are case insensitive.
+pub(crate) fn same_path(p1: &Path, p2: &Path) -> bool {
+    let normalize = |p: &Path| {
+        let path = fs::canonicalize(p)
+            .unwrap_or_else(|_| p.to_path_buf())
+            .to_string_lossy()
+            .trim_start_matches(r"\\?\")
+            .replace('\\', "/")
+            .trim_end_matches('/')
+            .to_string();
+        if cfg!(windows) {
+            path.to_lowercase()
+        } else {
+            path
+        }
+    };
+    normalize(p1) == normalize(p2)
+}
+
+impl LibraryFolder {
+    pub fn load() -> Result<Vec<LibraryFolder>, Error> {
+        let steam_dir = steam_dir();
+        let vdf = Vdf::load(&steam_dir.join("steamapps/libraryfolders.vdf"))?;
+        Ok(LibraryFolder::parse(&vdf, &steam_dir))
+    }
+
+    pub fn parse(vdf: &Vdf, steam_dir: &Path) -> Vec<LibraryFolder> {
+        let mut folders: Vec<(u32, LibraryFolder)> = Vec::new();
+        let mut current_format = false;
+        if let Some(entries) = vdf.map_entry(&["libraryfolders"]) {
+            for (key, value) in entries {
+                let index = match u32::from_str(key) {
+                    Ok(index) => index,
+                    Err(_) => continue,
+                };
+                let folder = match value {
+                    // Without a path steamapps() would resolve against the
+                    // working directory.
+                    Property::String(path) if path.is_empty() => continue,
+                    Property::String(path) => LibraryFolder {
+                        path: PathBuf::from(path),
+                        label: None,
+                        content_id: None,
+                        total_size: None,
+                        apps: HashMap::new(),
+                    },
+                    Property::Map(props) => {
+                        current_format = true;
+                        let string = |key: &str| match props.get(key) {
+                            Some(Property::String(value)) if !value.is_empty() => {
+                                Some(value.to_owned())
+                            }
+                            _ => None,
+                        };
+                        let mut apps = HashMap::new();
+                        if let Some(Property::Map(entries)) = props.get("apps") {
+                            for (app_id, size) in entries {
+                                if let (Ok(app_id), Property::String(size)) =
+                                    (u32::from_str(app_id), size)
+                                {
+                                    apps.insert(app_id, u64::from_str(size).unwrap_or(0));
+                                }
+                            }
+                        }
+                        let path = match string("path") {
+                            Some(path) => path,
+                            None => continue,
+                        };
+                        LibraryFolder {
+                            path: PathBuf::from(path),
+                            label: string("label"),
+                            content_id: string("contentid").and_then(|v| u64::from_str(&v).ok()),
+                            total_size: string("totalsize").and_then(|v| u64::from_str(&v).ok()),
+                            apps,
+                        }
+                    }
+                };
+                folders.push((index, folder));
+            }
+        }
+        folders.sort_unstable_by_key(|(index, _)| *index);
+        let mut folders: Vec<LibraryFolder> =
+            folders.into_iter().map(|(_, folder)| folder).collect();
+        // Only the old format leaves the Steam directory implied.
+        if !current_format && !folders.iter().any(|f| same_path(&f.path, steam_dir)) {
+            folders.insert(
+                0,
+                LibraryFolder {
+                    path: steam_dir.to_path_buf(),
+                    label: None,
+                    content_id: None,
+                    total_size: None,
+                    apps: HashMap::new(),
+                },
+            );
+        }
+        folders
+    }
+
+    /// Find a library by its path or label.
+    pub fn find(folders: Vec<LibraryFolder>, name: &str) -> Option<LibraryFolder> {
+        folders.into_iter().find(|f| {
+            same_path(&f.path, Path::new(name))
+                || f.label
+                    .as_deref()
+                    .map(|label| label.eq_ignore_ascii_case(name))
+                    .unwrap_or(false)
+        })
+    }
+
+    pub fn steamapps(&self) -> PathBuf {
+        self.path.join("steamapps")
+    }
+
+    pub fn is_steam_dir(&self) -> bool {
+        same_path(&self.path, &steam_dir())
+    }
+
+    /// Every appmanifest_<id>.acf in the library. Fails if any of them cannot
+    /// be read, as its install would otherwise look unaccounted for.
+    pub fn manifests(&self) -> Result<Vec<AppManifest>, Error> {
+        let mut manifests = Vec::new();
+        let steamapps = self.steamapps();
+        let entries = match fs::read_dir(&steamapps) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(manifests),
+            Err(e) => return Err(anyhow!("Unable to read {}: {}", steamapps.display(), e)),
+        };
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            if !(name.starts_with("appmanifest_") && name.ends_with(".acf")) {
+                continue;
+            }
+            manifests.push(AppManifest::load(&entry.path())?);
+        }
+        manifests.sort_unstable_by_key(|m| m.app_id);
+        Ok(manifests)
+    }
+
+    pub fn free_space(&self) -> Result<u64, Error> {
+        fs2::available_space(&self.path)
+            .map_err(|e| anyhow!("Unable to get free space of {}: {}", self.path.display(), e))
+    }
+
+    pub fn total_space(&self) -> Result<u64, Error> {
+        fs2::total_space(&self.path)
+            .map_err(|e| anyhow!("Unable to get size of {}: {}", self.path.display(), e))
+    }
+
+    /// Check the library's libraryfolder.vdf marker against what
+    /// libraryfolders.vdf says about the library.
+    pub fn check_marker(&self) -> MarkerStatus {
+        if self.is_steam_dir() {
+            return MarkerStatus::NotRequired;
+        }
+        let marker = self.path.join("libraryfolder.vdf");
+        if !marker.exists() {
+            return MarkerStatus::Missing;
+        }
+        let marker = match Vdf::load(&marker) {
+            Ok(marker) => marker,
+            Err(e) => return MarkerStatus::Inconsistent(e.to_string()),
+        };
+        let content_id = marker.u64_entry(&["libraryfolder", "contentid"]);
+        if self.content_id.is_some() && content_id != self.content_id {
+            return MarkerStatus::Inconsistent(format!(
+                "contentid {:?} does not match {:?}",
+                content_id, self.content_id
+            ));
+        }
+        let label = marker
+            .string_entry(&["libraryfolder", "label"])
+            .filter(|label| !label.is_empty());
+        if label != self.label {
+            return MarkerStatus::Inconsistent(format!(
+                "label {:?} does not match {:?}",
+                label, self.label
+            ));
+        }
+        MarkerStatus::Consistent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_old_format() {
+        let vdf = Vdf::parse(
+            r#"
+            "LibraryFolders"
+            {
+                "TimeNextStatsReport"	"1650000000"
+                "ContentStatsID"	"-123"
+                "2"	"E:\\SteamLibrary"
+                "1"	"D:\\Games"
+            }
+            "#,
+        )
+        .unwrap();
+        let folders = LibraryFolder::parse(&vdf, Path::new("C:/Steam"));
+        let paths: Vec<&Path> = folders.iter().map(|f| f.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            [
+                Path::new("C:/Steam"),
+                Path::new(r"D:\Games"),
+                Path::new(r"E:\SteamLibrary")
+            ]
+        );
+        assert!(folders
+            .iter()
+            .all(|f| f.label.is_none() && f.apps.is_empty()));
+    }
+
+    #[test]
+    fn parses_current_format() {
+        let vdf = Vdf::parse(
+            r#"
+            "libraryfolders"
+            {
+                "0"
+                {
+                    "path"		"C:\\Steam"
+                    "label"		""
+                    "contentid"		"4512345678901234567"
+                    "totalsize"		"0"
+                    "apps"
+                    {
+                        "220"		"4187000000"
+                    }
+                }
+                "1"
+                {
+                    "path"		"D:\\Games"
+                    "label"		"Games"
+                    "contentid"		"42"
+                    "totalsize"		"1000204886016"
+                    "apps"
+                    {
+                        "70"		"1024"
+                        "440"		"2048"
+                    }
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        let folders = LibraryFolder::parse(&vdf, Path::new("c:/steam/"));
+        assert_eq!(folders.len(), 2);
+        assert_eq!(folders[0].path, Path::new(r"C:\Steam"));
+        assert_eq!(folders[0].label, None);
+        assert_eq!(folders[0].content_id, Some(4512345678901234567));
+        assert_eq!(folders[0].apps.get(&220), Some(&4187000000));
+        assert_eq!(folders[1].path, Path::new(r"D:\Games"));
+        assert_eq!(folders[1].label.as_deref(), Some("Games"));
+        assert_eq!(folders[1].total_size, Some(1000204886016));
+        assert_eq!(folders[1].apps.len(), 2);
+        assert_eq!(folders[1].apps.get(&440), Some(&2048));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn does_not_repeat_a_symlinked_steam_dir() {
+        let dir = std::env::temp_dir().join(format!("steam_library_{}", std::process::id()));
+        let real = dir.join("Steam");
+        let link = dir.join("steam-link");
+        fs::create_dir_all(&real).unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        let vdf = Vdf::parse(&format!(
+            r#""libraryfolders" {{ "0" {{ "path" "{}" }} }}"#,
+            real.display()
+        ))
+        .unwrap();
+        let folders = LibraryFolder::parse(&vdf, &link);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(folders.len(), 1);
+        assert_eq!(folders[0].path, real);
+    }
+
+    #[test]
+    fn skips_entries_without_a_path() {
+        let vdf = Vdf::parse(
+            r#"
+            "libraryfolders"
+            {
+                "0" { "path" "/steam" }
+                "1" { "label" "broken" }
+                "2" { "path" "" }
+            }
+            "#,
+        )
+        .unwrap();
+        let folders = LibraryFolder::parse(&vdf, Path::new("/steam"));
+        assert_eq!(folders.len(), 1);
+        assert_eq!(folders[0].path, Path::new("/steam"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn paths_are_case_sensitive() {
+        let vdf = Vdf::parse(
+            r#"
+            "libraryfolders"
+            {
+                "0" { "path" "/steam" }
+                "1" { "path" "/mnt/Games" "label" "upper" }
+                "2" { "path" "/mnt/games" "label" "lower" }
+            }
+            "#,
+        )
+        .unwrap();
+        let folders = LibraryFolder::parse(&vdf, Path::new("/steam"));
+        let found = LibraryFolder::find(folders, "/mnt/games").unwrap();
+        assert_eq!(found.label.as_deref(), Some("lower"));
+    }
+}
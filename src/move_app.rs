@@ -0,0 +1,325 @@
+// Moves an installed app to another library folder: the install directory,
+// workshop content and the appmanifest/appworkshop files. Everything is
+// copied and checked before anything is removed from the source library.
+// Steam picks up the new location the next time it starts, so it must not
+// be running while files are moved under it.
+
+use crate::app_manifest::AppManifest;
+use crate::library_folder::LibraryFolder;
+use crate::orphans::dir_size;
+use anyhow::{anyhow, Error};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::str::FromStr;
+
+/// Whether the Steam client is running. Fails when that cannot be checked,
+/// callers about to change files under Steam should not assume it is not.
+/// The client runs as steam.exe, steam_osx or steam (Linux). This tool is
+/// called steam too, so its own process does not count.
+pub fn steam_running() -> Result<bool, Error> {
+    let pids: Vec<u32> = if cfg!(windows) {
+        let output = Command::new("tasklist")
+            .args(["/FI", "IMAGENAME eq steam.exe", "/FO", "CSV", "/NH"])
+            .output()
+            .map_err(|e| anyhow!("Unable to run tasklist: {}", e))?;
+        if !output.status.success() {
+            return Err(anyhow!("tasklist failed: {}", output.status));
+        }
+        // "steam.exe","1234","Console","1","123,456 K"
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split("\",\"").nth(1))
+            .filter_map(|pid| u32::from_str(pid).ok())
+            .collect()
+    } else {
+        let name = if cfg!(target_os = "macos") {
+            "steam_osx"
+        } else if cfg!(target_os = "linux") {
+            "steam"
+        } else {
+            return Err(anyhow!("Unable to tell whether Steam is running here"));
+        };
+        let output = Command::new("pgrep")
+            .args(["-x", name])
+            .output()
+            .map_err(|e| anyhow!("Unable to run pgrep: {}", e))?;
+        // pgrep exits with 1 when nothing matched and 2 or 3 on errors.
+        if !matches!(output.status.code(), Some(0) | Some(1)) {
+            return Err(anyhow!("pgrep failed: {}", output.status));
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|pid| u32::from_str(pid.trim()).ok())
+            .collect()
+    };
+    Ok(pids.into_iter().any(|pid| pid != process::id()))
+}
+
+// Symlinks are copied as links, games for Linux ship them and following
+// them would both change the install and fail the size check.
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> Result<(), Error> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn copy_symlink(from: &Path, to: &Path) -> Result<(), Error> {
+    let link = fs::read_link(from)?;
+    if from.is_dir() {
+        std::os::windows::fs::symlink_dir(link, to)?;
+    } else {
+        std::os::windows::fs::symlink_file(link, to)?;
+    }
+    Ok(())
+}
+
+fn copy_entry(from: &Path, to: &Path) -> Result<(), Error> {
+    let file_type = fs::symlink_metadata(from)?.file_type();
+    if file_type.is_symlink() {
+        copy_symlink(from, to)
+    } else if file_type.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_entry(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to)?;
+        Ok(())
+    }
+}
+
+// Unlike Path::exists this does not follow symlinks.
+fn exists(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
+fn remove(path: &Path) -> Result<(), Error> {
+    let is_dir = fs::symlink_metadata(path)
+        .map(|metadata| metadata.is_dir())
+        .unwrap_or(false);
+    let result = if is_dir {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    result.map_err(|e| anyhow!("Unable to remove {}: {}", path.display(), e))
+}
+
+// Everything that belongs to the app, in copy order: the appmanifest comes
+// last so the target library only lists the app once its data is there.
+fn app_paths(manifest: &AppManifest, steamapps: &Path) -> Vec<PathBuf> {
+    let workshop = steamapps.join("workshop");
+    vec![
+        steamapps.join("common").join(&manifest.install_dir),
+        workshop.join("content").join(manifest.app_id.to_string()),
+        workshop.join(format!("appworkshop_{}.acf", manifest.app_id)),
+        steamapps.join(format!("appmanifest_{}.acf", manifest.app_id)),
+    ]
+}
+
+pub fn move_app(manifest: &AppManifest, target: &LibraryFolder) -> Result<(), Error> {
+    if steam_running()? {
+        return Err(anyhow!("Steam is running, exit it before moving games"));
+    }
+    move_files(manifest, target)
+}
+
+fn move_files(manifest: &AppManifest, target: &LibraryFolder) -> Result<(), Error> {
+    if manifest.install_dir.is_empty() {
+        return Err(anyhow!("{} has no install directory", manifest.app_id));
+    }
+    if manifest.download_pending() {
+        return Err(anyhow!(
+            "{} has a download pending, let it finish first",
+            manifest.app_id
+        ));
+    }
+    let target_steamapps = target.steamapps();
+    let sources = app_paths(manifest, &manifest.library);
+    let targets = app_paths(manifest, &target_steamapps);
+    if sources[0] == targets[0] {
+        return Err(anyhow!(
+            "{} is already in {}",
+            manifest.app_id,
+            target.path.display()
+        ));
+    }
+    if let Some(existing) = targets.iter().find(|path| exists(path)) {
+        return Err(anyhow!("{} already exists", existing.display()));
+    }
+    let size: u64 = sources.iter().map(|path| dir_size(path)).sum();
+    let free = target.free_space()?;
+    if free < size {
+        return Err(anyhow!(
+            "{} needs {} bytes but only {} are free in {}",
+            manifest.app_id,
+            size,
+            free,
+            target.path.display()
+        ));
+    }
+
+    let copy = || -> Result<(), Error> {
+        for (source, target) in sources.iter().zip(&targets) {
+            if !exists(source) {
+                continue;
+            }
+            fs::create_dir_all(target.parent().unwrap())?;
+            copy_entry(source, target)?;
+            let (copied, expected) = (dir_size(target), dir_size(source));
+            if copied != expected {
+                return Err(anyhow!(
+                    "Copied {} bytes of {} to {}",
+                    copied,
+                    expected,
+                    target.display()
+                ));
+            }
+        }
+        Ok(())
+    };
+    if let Err(e) = copy() {
+        for target in targets.iter().filter(|path| exists(path)) {
+            if let Err(e) = remove(target) {
+                eprintln!("{}", e);
+            }
+        }
+        return Err(e);
+    }
+
+    // The appmanifest goes first so an interrupted removal leaves the app
+    // registered only in the target, with the rest showing up as orphans.
+    for source in sources.iter().rev().filter(|path| exists(path)) {
+        remove(source)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vdf::Vdf;
+    use std::collections::HashMap;
+
+    struct Fixture {
+        dir: PathBuf,
+        manifest: AppManifest,
+        target: LibraryFolder,
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    // A source library with app 10 installed, workshop content included,
+    // and an empty target library.
+    fn fixture(name: &str) -> Fixture {
+        let dir = std::env::temp_dir().join(format!("steam_move_{}_{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let steamapps = dir.join("source/steamapps");
+        let acf = r#""AppState" { "appid" "10" "installdir" "Game" "StateFlags" "4" }"#;
+        write(&steamapps.join("appmanifest_10.acf"), acf);
+        write(&steamapps.join("common/Game/game.bin"), "game data");
+        write(&steamapps.join("common/Game/data/level.pak"), "level");
+        write(&steamapps.join("workshop/content/10/1234/item.vpk"), "item");
+        write(&steamapps.join("workshop/appworkshop_10.acf"), "workshop");
+        fs::create_dir_all(dir.join("target/steamapps")).unwrap();
+        let manifest = AppManifest::parse(
+            &Vdf::parse(acf).unwrap(),
+            &steamapps.join("appmanifest_10.acf"),
+        )
+        .unwrap();
+        let target = LibraryFolder {
+            path: dir.join("target"),
+            label: None,
+            content_id: None,
+            total_size: None,
+            apps: HashMap::new(),
+        };
+        Fixture {
+            dir,
+            manifest,
+            target,
+        }
+    }
+
+    #[test]
+    fn moves_everything_and_removes_the_source() {
+        let fixture = fixture("moves");
+        #[cfg(unix)]
+        {
+            let game = fixture.manifest.library.join("common/Game");
+            std::os::unix::fs::symlink("game.bin", game.join("game.so")).unwrap();
+            std::os::unix::fs::symlink("data", game.join("data.lnk")).unwrap();
+        }
+        move_files(&fixture.manifest, &fixture.target).unwrap();
+
+        let source = app_paths(&fixture.manifest, &fixture.manifest.library);
+        assert!(source.iter().all(|path| !exists(path)));
+        let target = fixture.target.steamapps();
+        let read = |path: &str| fs::read_to_string(target.join(path)).unwrap();
+        assert_eq!(read("common/Game/game.bin"), "game data");
+        assert_eq!(read("common/Game/data/level.pak"), "level");
+        assert_eq!(read("workshop/content/10/1234/item.vpk"), "item");
+        assert_eq!(read("workshop/appworkshop_10.acf"), "workshop");
+        assert!(read("appmanifest_10.acf").contains("\"Game\""));
+        #[cfg(unix)]
+        {
+            let game = target.join("common/Game");
+            assert_eq!(
+                fs::read_link(game.join("game.so")).unwrap(),
+                Path::new("game.bin")
+            );
+            assert_eq!(
+                fs::read_link(game.join("data.lnk")).unwrap(),
+                Path::new("data")
+            );
+        }
+    }
+
+    #[test]
+    fn rolls_back_a_failed_copy() {
+        let fixture = fixture("rollback");
+        // The workshop content cannot be copied below a file.
+        write(&fixture.target.steamapps().join("workshop"), "in the way");
+        assert!(move_files(&fixture.manifest, &fixture.target).is_err());
+
+        let source = app_paths(&fixture.manifest, &fixture.manifest.library);
+        assert!(source.iter().all(|path| exists(path)));
+        let target = app_paths(&fixture.manifest, &fixture.target.steamapps());
+        assert!(target.iter().all(|path| !exists(path)));
+    }
+
+    #[test]
+    fn refuses_to_overwrite() {
+        let fixture = fixture("overwrite");
+        write(
+            &fixture.target.steamapps().join("common/Game/old.bin"),
+            "old",
+        );
+        assert!(move_files(&fixture.manifest, &fixture.target).is_err());
+        assert!(exists(&fixture.manifest.path));
+        assert!(exists(
+            &fixture.target.steamapps().join("common/Game/old.bin")
+        ));
+    }
+
+    #[test]
+    fn appmanifest_is_copied_last_and_removed_first() {
+        let fixture = fixture("order");
+        let paths = app_paths(&fixture.manifest, &fixture.manifest.library);
+        assert_eq!(paths.last(), Some(&fixture.manifest.path));
+        assert_eq!(paths[0], fixture.manifest.install_path());
+    }
+}
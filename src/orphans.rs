@@ -0,0 +1,232 @@
+// Data left behind in library folders that no appmanifest accounts for:
+//   steamapps/common/<dir>        - no appmanifest has it as installdir
+//   steamapps/downloading/<appid> - no download pending for the app
+//   steamapps/temp/<appid>        - same
+// Uninstalls and library moves tend to leave these around.
+
+use crate::app_manifest::AppManifest;
+use crate::library_folder::LibraryFolder;
+use anyhow::{anyhow, Error};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum OrphanKind {
+    InstallDir,
+    Download,
+    Temp,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Orphan {
+    pub path: PathBuf,
+    pub kind: OrphanKind,
+    pub size: u64,
+}
+
+/// Size of a file, or of everything below a directory.
+pub fn dir_size(path: &Path) -> u64 {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return 0,
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| dir_size(&e.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+// Entries in downloading/ and temp/ are named after the app ("440") or
+// carry it in their name ("state_440_441.patch").
+fn app_id_of(name: &str) -> Option<u32> {
+    let name = name.strip_prefix("state_").unwrap_or(name);
+    let digits: String = name.chars().take_while(|c| c.is_ascii_digit()).collect();
+    u32::from_str(&digits).ok()
+}
+
+fn stray_entries(
+    dir: &Path,
+    kind: OrphanKind,
+    manifests: &[AppManifest],
+    orphans: &mut Vec<Orphan>,
+) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let app_id = match app_id_of(&entry.file_name().to_string_lossy()) {
+            Some(app_id) => app_id,
+            None => continue,
+        };
+        let pending = manifests
+            .iter()
+            .any(|m| m.app_id == app_id && m.download_pending());
+        if !pending {
+            orphans.push(Orphan {
+                size: dir_size(&entry.path()),
+                path: entry.path(),
+                kind,
+            });
+        }
+    }
+}
+
+/// Fails if an appmanifest cannot be read, the install it describes would
+/// be reported as orphaned.
+pub fn find(folders: &[LibraryFolder]) -> Result<Vec<Orphan>, Error> {
+    let mut orphans = Vec::new();
+    for folder in folders {
+        let manifests = folder.manifests()?;
+        let steamapps = folder.steamapps();
+        if let Ok(entries) = fs::read_dir(steamapps.join("common")) {
+            for entry in entries.filter_map(|e| e.ok()) {
+                if !entry.path().is_dir() {
+                    continue;
+                }
+                let name = entry.file_name().to_string_lossy().to_lowercase();
+                if !manifests
+                    .iter()
+                    .any(|m| m.install_dir.to_lowercase() == name)
+                {
+                    orphans.push(Orphan {
+                        size: dir_size(&entry.path()),
+                        path: entry.path(),
+                        kind: OrphanKind::InstallDir,
+                    });
+                }
+            }
+        }
+        stray_entries(
+            &steamapps.join("downloading"),
+            OrphanKind::Download,
+            &manifests,
+            &mut orphans,
+        );
+        stray_entries(
+            &steamapps.join("temp"),
+            OrphanKind::Temp,
+            &manifests,
+            &mut orphans,
+        );
+    }
+    Ok(orphans)
+}
+
+impl Orphan {
+    pub fn delete(&self) -> Result<(), Error> {
+        let result = if self.path.is_dir() {
+            fs::remove_dir_all(&self.path)
+        } else {
+            fs::remove_file(&self.path)
+        };
+        result.map_err(|e| anyhow!("Unable to delete {}: {}", self.path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vdf::Vdf;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    // A Steam directory with app 10 installed and app 20 downloading.
+    fn library(dir: &Path) -> PathBuf {
+        let _ = fs::remove_dir_all(dir);
+        let steam = dir.join("Steam");
+        let steamapps = steam.join("steamapps");
+        write(
+            &steamapps.join("appmanifest_10.acf"),
+            r#""AppState" { "appid" "10" "installdir" "Game" "StateFlags" "4" }"#,
+        );
+        write(
+            &steamapps.join("appmanifest_20.acf"),
+            r#""AppState" { "appid" "20" "installdir" "Next" "StateFlags" "1026" }"#,
+        );
+        write(&steamapps.join("common/Game/game.bin"), "game");
+        write(&steamapps.join("common/Leftover/old.bin"), "leftover");
+        write(&steamapps.join("downloading/20/chunk"), "pending");
+        write(&steamapps.join("downloading/state_20_21.patch"), "pending");
+        write(&steamapps.join("downloading/30/chunk"), "stale");
+        write(&steamapps.join("temp/30/chunk"), "stale");
+        steam
+    }
+
+    // Orphans found in the library at `dir`, whose libraryfolders.vdf lists
+    // the real Steam directory, when Steam is found at `steam_dir`.
+    fn orphans(dir: &Path, steam_dir: &Path) -> Vec<(OrphanKind, String, u64)> {
+        let vdf = Vdf::parse(&format!(
+            r#""libraryfolders" {{ "0" {{ "path" "{}" }} }}"#,
+            dir.join("Steam").display()
+        ))
+        .unwrap();
+        let folders = LibraryFolder::parse(&vdf, steam_dir);
+        let mut orphans: Vec<(OrphanKind, String, u64)> = find(&folders)
+            .unwrap()
+            .into_iter()
+            .map(|o| {
+                let name = o.path.file_name().unwrap().to_string_lossy().into_owned();
+                (o.kind, name, o.size)
+            })
+            .collect();
+        orphans.sort_by_key(|(kind, name, _)| (name.clone(), *kind as u8));
+        orphans
+    }
+
+    fn expected() -> Vec<(OrphanKind, String, u64)> {
+        vec![
+            (OrphanKind::Download, "30".to_string(), 5),
+            (OrphanKind::Temp, "30".to_string(), 5),
+            (OrphanKind::InstallDir, "Leftover".to_string(), 8),
+        ]
+    }
+
+    #[test]
+    fn finds_data_no_manifest_refers_to() {
+        let dir = std::env::temp_dir().join(format!("steam_orphans_{}", std::process::id()));
+        let steam = library(&dir);
+        let found = orphans(&dir, &steam);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found, expected());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reports_each_orphan_once_through_a_symlinked_steam_dir() {
+        let dir = std::env::temp_dir().join(format!("steam_orphans_link_{}", std::process::id()));
+        let steam = library(&dir);
+        // ~/.steam/steam is a link to the real Steam directory.
+        let link = dir.join("steam-link");
+        std::os::unix::fs::symlink(&steam, &link).unwrap();
+        let found = orphans(&dir, &link);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found, expected());
+    }
+
+    #[test]
+    fn fails_on_unreadable_manifests() {
+        let dir = std::env::temp_dir().join(format!("steam_orphans_bad_{}", std::process::id()));
+        let steam = library(&dir);
+        write(
+            &steam.join("steamapps/appmanifest_30.acf"),
+            r#""AppState" {"#,
+        );
+        let folders = LibraryFolder::parse(&Vdf::parse("").unwrap(), &steam);
+        let result = find(&folders);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,330 @@
+// Works out how the logged in account came to have each app.
+//
+// The authoritative license list lives encrypted in appcache/licensecache,
+// so this works from what the client leaves around in plain text:
+//   config/loginusers.vdf - accounts, the one with "MostRecent" "1" is active
+//   userdata/<account id>/config/localconfig.vdf
+//     "Licenses"   - package ids the account holds a license for
+//     "apptickets" - app ids the client fetched ownership tickets for
+//   steamapps/appmanifest_<id>.acf
+//     "LastOwner"  - steam id of the account whose license installed the app
+// Packages that merely show up in packageinfo.vdf (store browsing, free
+// weekends that ended, family members' libraries) are not enough.
+
+use crate::app_info::AppInfo;
+use crate::package_info::{BillingType, PackageInfo};
+use crate::steam_dir::steam_dir;
+use crate::vdf::{Property, Vdf};
+use anyhow::{anyhow, Error};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+// Account ids in userdata are the low 32 bits of the 64 bit steam id.
+const STEAM_ID_ACCOUNT_MASK: u64 = 0xFFFF_FFFF;
+
+/// The account id used for the account's userdata directory.
+pub fn account_id(steam_id: u64) -> u32 {
+    (steam_id & STEAM_ID_ACCOUNT_MASK) as u32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Ownership {
+    Owned,
+    FreeToPlay,
+    /// Installed with another account's license (the appmanifest's
+    /// `LastOwner`). The client keeps no plain text record of which apps a
+    /// family member shares, so shared games that are not installed are
+    /// `Unknown`.
+    FamilyShared,
+    FreeWeekend,
+    /// No license of the account was found for the app.
+    Unknown,
+}
+
+impl FromStr for Ownership {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Ownership, Error> {
+        match s.to_lowercase().replace(&['-', '_'][..], "").as_str() {
+            "owned" => Ok(Ownership::Owned),
+            "freetoplay" | "f2p" => Ok(Ownership::FreeToPlay),
+            "familyshared" | "shared" => Ok(Ownership::FamilyShared),
+            "freeweekend" => Ok(Ownership::FreeWeekend),
+            "unknown" => Ok(Ownership::Unknown),
+            _ => Err(anyhow!("Unknown ownership: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Licenses {
+    pub steam_id: Option<u64>,
+    pub package_ids: HashSet<u32>,
+    pub app_ids: HashSet<u32>,
+}
+
+impl Licenses {
+    /// Load the license data of the most recently logged in account. When
+    /// there is no such account, nothing is known to be licensed.
+    pub fn load() -> Result<Licenses, Error> {
+        let login_users = Vdf::load(&steam_dir().join("config/loginusers.vdf"))?;
+        let steam_id = match Licenses::active_user(&login_users) {
+            Some(steam_id) => steam_id,
+            None => return Ok(Licenses::default()),
+        };
+        let local_config = steam_dir()
+            .join("userdata")
+            .join(account_id(steam_id).to_string())
+            .join("config/localconfig.vdf");
+        if !local_config.exists() {
+            return Ok(Licenses {
+                steam_id: Some(steam_id),
+                ..Licenses::default()
+            });
+        }
+        Ok(Licenses::parse(steam_id, &Vdf::load(&local_config)?))
+    }
+
+    /// The steam id of the account marked "MostRecent" in loginusers.vdf,
+    /// or the first account if none is.
+    pub fn active_user(login_users: &Vdf) -> Option<u64> {
+        let users = login_users.map_entry(&["users"])?;
+        let mut steam_id = None;
+        for (id, user) in users {
+            let id = match u64::from_str(id) {
+                Ok(id) => id,
+                Err(_) => continue,
+            };
+            if let Property::Map(user) = user {
+                let most_recent = user
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case("mostrecent"))
+                    .map(|(_, v)| matches!(v, Property::String(s) if s == "1"))
+                    .unwrap_or(false);
+                if most_recent || steam_id.is_none() {
+                    steam_id = Some(id);
+                }
+                if most_recent {
+                    break;
+                }
+            }
+        }
+        steam_id
+    }
+
+    /// The licenses of `steam_id` from its localconfig.vdf.
+    pub fn parse(steam_id: u64, local_config: &Vdf) -> Licenses {
+        let mut licenses = Licenses {
+            steam_id: Some(steam_id),
+            ..Licenses::default()
+        };
+        if let Some(packages) = local_config.map_entry(&["UserLocalConfigStore", "Licenses"]) {
+            licenses.package_ids = packages
+                .keys()
+                .filter_map(|k| u32::from_str(k).ok())
+                .collect();
+        }
+        if let Some(tickets) = local_config.map_entry(&["UserLocalConfigStore", "apptickets"]) {
+            licenses.app_ids = tickets
+                .keys()
+                .filter_map(|k| u32::from_str(k).ok())
+                .collect();
+        }
+        licenses
+    }
+
+    /// `packages` are the packages granting the app and `last_owner` is the
+    /// `LastOwner` of its appmanifest when the app is installed.
+    pub fn ownership(
+        &self,
+        app_info: &AppInfo,
+        packages: &[&PackageInfo],
+        last_owner: Option<u64>,
+    ) -> Ownership {
+        let app_id = app_info.u32_entry(&["appinfo", "appid"]).unwrap_or(0);
+        if let (Some(owner), Some(steam_id)) = (last_owner, self.steam_id) {
+            if owner != 0 && owner != steam_id {
+                return Ownership::FamilyShared;
+            }
+        }
+        let free_to_play = app_info
+            .string_entry(&["appinfo", "common", "freetoplay"])
+            .map(|v| v == "1")
+            .unwrap_or(false);
+        let licensed: Vec<&PackageInfo> = packages
+            .iter()
+            .copied()
+            .filter(|p| self.package_ids.contains(&p.id))
+            .collect();
+        if !licensed.is_empty() {
+            if licensed.iter().all(|p| p.extended().free_weekend) {
+                return Ownership::FreeWeekend;
+            }
+            let is_free = |p: &&PackageInfo| {
+                matches!(
+                    p.billing_type(),
+                    BillingType::NoCost | BillingType::FreeOnDemand
+                )
+            };
+            if free_to_play || licensed.iter().all(is_free) {
+                return Ownership::FreeToPlay;
+            }
+            return Ownership::Owned;
+        }
+        if self.app_ids.contains(&app_id) || (last_owner.is_some() && last_owner == self.steam_id) {
+            if free_to_play {
+                return Ownership::FreeToPlay;
+            }
+            return Ownership::Owned;
+        }
+        Ownership::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_info::Property as Prop;
+    use std::collections::HashMap;
+
+    const STEAM_ID: u64 = 76561197960287930;
+    const OTHER_STEAM_ID: u64 = 76561197960287931;
+
+    fn map(entries: Vec<(&str, Prop)>) -> HashMap<String, Prop> {
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect()
+    }
+
+    fn app(app_id: u32, free_to_play: bool) -> AppInfo {
+        let mut common = vec![("name", Prop::String("Game".to_string()))];
+        if free_to_play {
+            common.push(("freetoplay", Prop::String("1".to_string())));
+        }
+        AppInfo {
+            state: 2,
+            last_updated: 0,
+            access_token: 0,
+            checksum: [0; 20],
+            change_no: 0,
+            props: map(vec![(
+                "appinfo",
+                Prop::Map(map(vec![
+                    ("appid", Prop::Uint32(app_id)),
+                    ("common", Prop::Map(map(common))),
+                ])),
+            )]),
+        }
+    }
+
+    fn package(id: u32, billing_type: u32, free_weekend: bool) -> PackageInfo {
+        let extended = if free_weekend {
+            vec![("freeweekend", Prop::String("1".to_string()))]
+        } else {
+            vec![]
+        };
+        PackageInfo {
+            id,
+            props: map(vec![
+                ("packageid", Prop::Uint32(id)),
+                ("billingtype", Prop::Uint32(billing_type)),
+                ("extended", Prop::Map(map(extended))),
+            ]),
+        }
+    }
+
+    fn licenses() -> Licenses {
+        let login_users = Vdf::parse(&format!(
+            r#"
+            "users"
+            {{
+                "{}" {{ "AccountName" "other" "MostRecent" "0" }}
+                "{}" {{ "AccountName" "me" "MostRecent" "1" }}
+            }}
+            "#,
+            OTHER_STEAM_ID, STEAM_ID
+        ))
+        .unwrap();
+        let steam_id = Licenses::active_user(&login_users).unwrap();
+        let local_config = Vdf::parse(
+            r#"
+            "UserLocalConfigStore"
+            {
+                "Licenses"
+                {
+                    "100" { "TimeCreated" "1" }
+                    "200" { "TimeCreated" "1" }
+                    "300" { "TimeCreated" "1" }
+                }
+                "apptickets"
+                {
+                    "40" "ticket"
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        Licenses::parse(steam_id, &local_config)
+    }
+
+    #[test]
+    fn reads_the_active_account_and_its_licenses() {
+        let licenses = licenses();
+        assert_eq!(licenses.steam_id, Some(STEAM_ID));
+        assert_eq!(licenses.package_ids.len(), 3);
+        assert!(licenses.package_ids.contains(&200));
+        assert!(licenses.app_ids.contains(&40));
+    }
+
+    #[test]
+    fn derives_ownership() {
+        let licenses = licenses();
+        let bought = package(100, 1, false);
+        let free = package(200, 12, false);
+        let free_weekend = package(300, 1, true);
+        let not_licensed = package(999, 1, false);
+
+        assert_eq!(
+            licenses.ownership(&app(10, false), &[&bought], None),
+            Ownership::Owned
+        );
+        assert_eq!(
+            licenses.ownership(&app(10, false), &[&free_weekend, &bought], None),
+            Ownership::Owned
+        );
+        assert_eq!(
+            licenses.ownership(&app(20, false), &[&free], None),
+            Ownership::FreeToPlay
+        );
+        assert_eq!(
+            licenses.ownership(&app(20, true), &[&bought], None),
+            Ownership::FreeToPlay
+        );
+        assert_eq!(
+            licenses.ownership(&app(30, false), &[&free_weekend], None),
+            Ownership::FreeWeekend
+        );
+        assert_eq!(
+            licenses.ownership(&app(40, false), &[&not_licensed], None),
+            Ownership::Owned
+        );
+        assert_eq!(
+            licenses.ownership(&app(50, false), &[&not_licensed], Some(STEAM_ID)),
+            Ownership::Owned
+        );
+        assert_eq!(
+            licenses.ownership(&app(50, false), &[&bought], Some(OTHER_STEAM_ID)),
+            Ownership::FamilyShared
+        );
+        assert_eq!(
+            licenses.ownership(&app(60, false), &[&not_licensed], None),
+            Ownership::Unknown
+        );
+        assert_eq!(
+            Licenses::default().ownership(&app(10, false), &[&bought], None),
+            Ownership::Unknown
+        );
+    }
+}
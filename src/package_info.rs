@@ -3,11 +3,17 @@
 // https://github.com/leovp/steamfiles/issues/3
 // https://github.com/ValvePython/vdf/issues/13
 
+pub use crate::app_info::Property;
+
+use crate::app_info::id_list;
+use crate::steam_dir::steam_dir;
 use anyhow::{anyhow, Error};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::convert::TryInto;
 use std::fs;
 use std::io::Read;
+use std::str::FromStr;
 
 fn u8(buf: &[u8], pos: &mut usize) -> u8 {
     let value = buf[*pos];
@@ -33,14 +39,6 @@ fn le_u64(buf: &[u8], pos: &mut usize) -> u64 {
     value
 }
 
-#[derive(Debug)]
-pub enum Property {
-    Uint32(u32),
-    Uint64(u64),
-    Map(HashMap<String, Property>),
-    String(String),
-}
-
 fn string(buf: &[u8], pos: &mut usize) -> Result<String, Error> {
     let begin = *pos;
     loop {
@@ -54,6 +52,145 @@ fn string(buf: &[u8], pos: &mut usize) -> Result<String, Error> {
     Ok(value)
 }
 
+// EBillingType from the Steamworks SDK.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum BillingType {
+    NoCost,
+    BillOnceOnly,
+    BillMonthly,
+    ProofOfPrepurchaseOnly,
+    GuestPass,
+    HardwarePromo,
+    Gift,
+    AutoGrant,
+    OemTicket,
+    RecurringOption,
+    BillOnceOrCdKey,
+    Repurchaseable,
+    FreeOnDemand,
+    Rental,
+    CommercialLicense,
+    FreeCommercialLicense,
+    Unknown(u32),
+}
+
+impl From<u32> for BillingType {
+    fn from(value: u32) -> BillingType {
+        match value {
+            0 => BillingType::NoCost,
+            1 => BillingType::BillOnceOnly,
+            2 => BillingType::BillMonthly,
+            3 => BillingType::ProofOfPrepurchaseOnly,
+            4 => BillingType::GuestPass,
+            5 => BillingType::HardwarePromo,
+            6 => BillingType::Gift,
+            7 => BillingType::AutoGrant,
+            8 => BillingType::OemTicket,
+            9 => BillingType::RecurringOption,
+            10 => BillingType::BillOnceOrCdKey,
+            11 => BillingType::Repurchaseable,
+            12 => BillingType::FreeOnDemand,
+            13 => BillingType::Rental,
+            14 => BillingType::CommercialLicense,
+            15 => BillingType::FreeCommercialLicense,
+            _ => BillingType::Unknown(value),
+        }
+    }
+}
+
+// ELicenseType from the Steamworks SDK.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum LicenseType {
+    NoLicense,
+    SinglePurchase,
+    SinglePurchaseLimitedUse,
+    RecurringCharge,
+    RecurringChargeLimitedUse,
+    RecurringChargeLimitedUseWithOverages,
+    RecurringOption,
+    LimitedUseDelayedActivation,
+    Unknown(u32),
+}
+
+impl From<u32> for LicenseType {
+    fn from(value: u32) -> LicenseType {
+        match value {
+            0 => LicenseType::NoLicense,
+            1 => LicenseType::SinglePurchase,
+            2 => LicenseType::SinglePurchaseLimitedUse,
+            3 => LicenseType::RecurringCharge,
+            4 => LicenseType::RecurringChargeLimitedUse,
+            5 => LicenseType::RecurringChargeLimitedUseWithOverages,
+            6 => LicenseType::RecurringOption,
+            7 => LicenseType::LimitedUseDelayedActivation,
+            _ => LicenseType::Unknown(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PackageStatus {
+    Available,
+    Preorder,
+    Unavailable,
+    Invalid,
+    Unknown(u32),
+}
+
+impl From<u32> for PackageStatus {
+    fn from(value: u32) -> PackageStatus {
+        match value {
+            0 => PackageStatus::Available,
+            1 => PackageStatus::Preorder,
+            2 => PackageStatus::Unavailable,
+            3 => PackageStatus::Invalid,
+            _ => PackageStatus::Unknown(value),
+        }
+    }
+}
+
+// The `extended` section is a loose bag of flags. The commonly useful ones
+// are typed, everything is available as text in `values`.
+#[derive(Debug, Serialize)]
+pub struct Extended {
+    pub expiry_time: Option<u32>,
+    pub start_time: Option<u32>,
+    pub free_weekend: bool,
+    pub allow_cross_region_trading_and_gifting: bool,
+    pub values: HashMap<String, String>,
+}
+
+impl Extended {
+    fn from_props(props: Option<&HashMap<String, Property>>) -> Extended {
+        let mut values = HashMap::new();
+        if let Some(props) = props {
+            for (key, value) in props {
+                let value = match value {
+                    Property::Uint32(uint32) => uint32.to_string(),
+                    Property::Uint64(uint64) => uint64.to_string(),
+                    Property::String(string) => string.to_owned(),
+                    Property::Map(_) => continue,
+                };
+                values.insert(key.to_lowercase(), value);
+            }
+        }
+        let number = |key: &str| values.get(key).and_then(|v| u32::from_str(v).ok());
+        let flag = |key: &str| {
+            values
+                .get(key)
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false)
+        };
+        Extended {
+            expiry_time: number("expirytime"),
+            start_time: number("starttime"),
+            free_weekend: flag("freeweekend"),
+            allow_cross_region_trading_and_gifting: flag("allowcrossregiontradingandgifting"),
+            values,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PackageInfo {
     pub id: u32,
@@ -63,8 +200,7 @@ pub struct PackageInfo {
 impl PackageInfo {
     pub fn load() -> Result<Vec<PackageInfo>, Error> {
         let mut buf = Vec::new();
-        fs::File::open("c:/program files (x86)/steam/appcache/packageinfo.vdf")?
-            .read_to_end(&mut buf)?;
+        fs::File::open(steam_dir().join("appcache/packageinfo.vdf"))?.read_to_end(&mut buf)?;
         let mut pos = 0;
         let version = u8(&buf, &mut pos);
         // Doc only knows about 24 and 26. My file has 27. What other diffs are there?
@@ -201,6 +337,57 @@ impl PackageInfo {
         }
     }
 
+    pub fn package_id(&self) -> u32 {
+        self.u32_entry(&["packageid"]).unwrap_or(self.id)
+    }
+
+    pub fn billing_type(&self) -> BillingType {
+        BillingType::from(self.u32_entry(&["billingtype"]).unwrap_or(0))
+    }
+
+    pub fn license_type(&self) -> LicenseType {
+        LicenseType::from(self.u32_entry(&["licensetype"]).unwrap_or(0))
+    }
+
+    pub fn status(&self) -> PackageStatus {
+        PackageStatus::from(self.u32_entry(&["status"]).unwrap_or(0))
+    }
+
+    pub fn app_ids(&self) -> Vec<u32> {
+        self.map_entry(&["appids"]).map(id_list).unwrap_or_default()
+    }
+
+    /// Map every app id to the ids of the packages that grant it. Bundles and
+    /// complete packs grant many apps and an app is often in many packages.
+    pub fn packages_by_app(pkg_infos: &[PackageInfo]) -> HashMap<u32, Vec<u32>> {
+        let mut packages_by_app: HashMap<u32, Vec<u32>> = HashMap::new();
+        for pkg_info in pkg_infos {
+            for app_id in pkg_info.app_ids() {
+                let packages = packages_by_app.entry(app_id).or_default();
+                if !packages.contains(&pkg_info.id) {
+                    packages.push(pkg_info.id);
+                }
+            }
+        }
+        packages_by_app
+    }
+
+    pub fn depot_ids(&self) -> Vec<u32> {
+        self.map_entry(&["depotids"])
+            .map(id_list)
+            .unwrap_or_default()
+    }
+
+    pub fn app_items(&self) -> Vec<u32> {
+        self.map_entry(&["appitems"])
+            .map(id_list)
+            .unwrap_or_default()
+    }
+
+    pub fn extended(&self) -> Extended {
+        Extended::from_props(self.map_entry(&["extended"]))
+    }
+
     pub fn map_entry(&self, path: &[&str]) -> Option<&HashMap<String, Property>> {
         match self.entry(path) {
             Some(Property::Map(map)) => Some(map),
@@ -0,0 +1,38 @@
+// Platforms from appinfo's `common/oslist` ("windows,macos,linux").
+
+use anyhow::{anyhow, Error};
+use serde::Serialize;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Platform {
+    Windows,
+    MacOs,
+    Linux,
+}
+
+impl FromStr for Platform {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Platform, Error> {
+        match s.trim().to_lowercase().as_str() {
+            "windows" | "win" => Ok(Platform::Windows),
+            "macos" | "mac" | "osx" => Ok(Platform::MacOs),
+            "linux" | "steamos" => Ok(Platform::Linux),
+            _ => Err(anyhow!("Unknown platform: {}", s)),
+        }
+    }
+}
+
+impl Platform {
+    /// The platform this binary was built for.
+    pub fn current() -> Platform {
+        if cfg!(target_os = "linux") {
+            Platform::Linux
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Windows
+        }
+    }
+}
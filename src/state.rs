@@ -0,0 +1,121 @@
+// Bit flags for the state of an app: `StateFlags` in appmanifest_<id>.acf
+// (EAppState in the Steamworks SDK) and the state stored with each entry of
+// appinfo.vdf.
+
+use bitflags::bitflags;
+use serde::Serialize;
+use std::fmt;
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct AppState: u32 {
+        const UNINSTALLED = 0x1;
+        const UPDATE_REQUIRED = 0x2;
+        const FULLY_INSTALLED = 0x4;
+        const ENCRYPTED = 0x8;
+        const LOCKED = 0x10;
+        const FILES_MISSING = 0x20;
+        const APP_RUNNING = 0x40;
+        const FILES_CORRUPT = 0x80;
+        const UPDATE_RUNNING = 0x100;
+        const UPDATE_PAUSED = 0x200;
+        const UPDATE_STARTED = 0x400;
+        const UNINSTALLING = 0x800;
+        const BACKUP_RUNNING = 0x1000;
+        const RECONFIGURING = 0x10000;
+        const VALIDATING = 0x20000;
+        const ADDING_FILES = 0x40000;
+        const PREALLOCATING = 0x80000;
+        const DOWNLOADING = 0x100000;
+        const STAGING = 0x200000;
+        const COMMITTING = 0x400000;
+        const UPDATE_STOPPING = 0x800000;
+    }
+}
+
+bitflags! {
+    // Not documented by Valve. Entries with complete metadata are 0x2,
+    // 0x1 shows up for entries the client only holds partial data for.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct AppInfoState: u32 {
+        const PARTIAL = 0x1;
+        const COMPLETE = 0x2;
+    }
+}
+
+// Renders flags as "fully installed, update required", with any unknown
+// bits as hex.
+fn write_flags<'a>(
+    f: &mut fmt::Formatter,
+    names: impl Iterator<Item = &'a str>,
+    unknown: u32,
+) -> fmt::Result {
+    let mut parts: Vec<String> = names
+        .map(|name| name.to_lowercase().replace('_', " "))
+        .collect();
+    if unknown != 0 {
+        parts.push(format!("{:#x}", unknown));
+    }
+    if parts.is_empty() {
+        write!(f, "none")
+    } else {
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+impl fmt::Display for AppState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_flags(
+            f,
+            self.iter_names().map(|(name, _)| name),
+            self.bits() & !AppState::all().bits(),
+        )
+    }
+}
+
+impl fmt::Display for AppInfoState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_flags(
+            f,
+            self.iter_names().map(|(name, _)| name),
+            self.bits() & !AppInfoState::all().bits(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum InstallState {
+    NotInstalled,
+    Installed(AppState),
+}
+
+impl InstallState {
+    pub fn is_installed(&self) -> bool {
+        matches!(self, InstallState::Installed(_))
+    }
+
+    /// Installed with all content present and no update or repair pending.
+    pub fn is_ready(&self) -> bool {
+        match self {
+            InstallState::NotInstalled => false,
+            InstallState::Installed(state) => {
+                state.contains(AppState::FULLY_INSTALLED)
+                    && !state.intersects(
+                        AppState::UPDATE_REQUIRED
+                            | AppState::FILES_MISSING
+                            | AppState::FILES_CORRUPT
+                            | AppState::UNINSTALLING,
+                    )
+            }
+        }
+    }
+}
+
+impl fmt::Display for InstallState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InstallState::NotInstalled => write!(f, "not installed"),
+            InstallState::Installed(state) => write!(f, "{}", state),
+        }
+    }
+}
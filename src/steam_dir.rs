@@ -0,0 +1,31 @@
+// Where the Steam client keeps its data. STEAM_DIR overrides the platform
+// default, which on Linux is the ~/.steam/steam link the client maintains
+// or its usual targets (native and Flatpak).
+
+use std::env;
+use std::path::PathBuf;
+
+pub fn steam_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("STEAM_DIR") {
+        return PathBuf::from(dir);
+    }
+    if cfg!(windows) {
+        return PathBuf::from("c:/program files (x86)/steam");
+    }
+    let home = dirs::home_dir().unwrap_or_default();
+    if cfg!(target_os = "macos") {
+        return home.join("Library/Application Support/Steam");
+    }
+    let candidates = [
+        home.join(".steam/steam"),
+        dirs::data_dir()
+            .unwrap_or_else(|| home.join(".local/share"))
+            .join("Steam"),
+        home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+    ];
+    candidates
+        .iter()
+        .find(|dir| dir.exists())
+        .unwrap_or(&candidates[0])
+        .clone()
+}
@@ -1,59 +1,186 @@
 use crate::app_info::AppInfo;
+use crate::app_manifest::AppManifest;
+use crate::artwork::Artwork;
+use crate::categories::{Category, Genre};
+use crate::controller::{ControllerSupport, SteamInput};
+use crate::deck::{DeckCategory, DeckCompatibility};
+use crate::depot::Depot;
+use crate::icon::icon_path;
+use crate::languages::Language;
+use crate::library_folder::LibraryFolder;
+use crate::ownership::{account_id, Licenses, Ownership};
 use crate::package_info::PackageInfo;
+use crate::platform::Platform;
+use crate::state::InstallState;
+use crate::steam_dir::steam_dir;
+use crate::update_status::UpdateStatus;
+use crate::vr::VrSupport;
 use anyhow::Error;
+use chrono::{DateTime, Utc};
 use serde::Serialize;
-use std::fs;
-use std::io::{BufRead, BufReader};
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::str::FromStr;
+
+#[derive(Serialize)]
+pub struct Dlc {
+    pub id: u32,
+    pub title: Option<String>,
+    pub owned: bool,
+    pub installed: bool,
+}
+
+#[derive(Serialize)]
+pub struct DepotStatus {
+    pub depot_id: u32,
+    pub name: Option<String>,
+    pub dlc_app_id: Option<u32>,
+    pub size: u64,
+    pub installed_manifest: u64,
+    pub latest_manifest: Option<u64>,
+    // The installed manifest is not the one of the installed branch.
+    pub stale: bool,
+}
 
 #[derive(Serialize)]
 pub struct SteamGame {
     pub id: u32,
     pub title: String,
     pub logo: Option<String>,
-    pub installed: bool,
+    pub icon: Option<String>,
+    pub artwork: Artwork,
+    pub installed: InstallState,
+    pub install_path: Option<PathBuf>,
+    pub manifest: Option<AppManifest>,
+    #[serde(skip)]
+    pub depots: Vec<Depot>,
+    pub update_status: Option<UpdateStatus>,
+    pub packages: Vec<u32>,
+    pub ownership: Ownership,
+    pub dlc: Vec<Dlc>,
+    pub store_tags: Vec<u32>,
+    pub genres: Vec<Genre>,
+    pub primary_genre: Option<Genre>,
+    pub categories: Vec<Category>,
+    pub deck: Option<DeckCompatibility>,
+    pub developers: Vec<String>,
+    pub publishers: Vec<String>,
+    pub franchises: Vec<String>,
+    pub release_date: Option<DateTime<Utc>>,
+    pub original_release_date: Option<DateTime<Utc>>,
+    pub metacritic_score: Option<u32>,
+    pub review_score: Option<u32>,
+    pub review_percentage: Option<u32>,
+    pub languages: Vec<Language>,
+    pub platforms: Vec<Platform>,
+    pub os_arch: Option<String>,
+    pub controller_support: ControllerSupport,
+    pub steam_input: SteamInput,
+    pub vr: VrSupport,
 }
 
 impl SteamGame {
+    pub fn has_audio_in(&self, language: &str) -> bool {
+        self.languages
+            .iter()
+            .any(|l| l.full_audio && l.name.eq_ignore_ascii_case(language))
+    }
+
+    /// The depots recorded in the appmanifest, matched against the depots
+    /// appinfo lists for the installed branch.
+    pub fn installed_depots(&self) -> Vec<DepotStatus> {
+        let manifest = match &self.manifest {
+            Some(manifest) => manifest,
+            None => return Vec::new(),
+        };
+        manifest
+            .installed_depots
+            .iter()
+            .map(|installed| {
+                let depot = self.depots.iter().find(|d| d.id == installed.depot_id);
+                let latest_manifest = depot
+                    .and_then(|d| d.manifests.get(manifest.branch()))
+                    .map(|m| m.gid);
+                DepotStatus {
+                    depot_id: installed.depot_id,
+                    name: depot.and_then(|d| d.name.clone()),
+                    dlc_app_id: installed
+                        .dlc_app_id
+                        .or_else(|| depot.and_then(|d| d.dlc_app_id)),
+                    size: installed.size,
+                    installed_manifest: installed.manifest,
+                    latest_manifest,
+                    stale: latest_manifest
+                        .map(|gid| gid != installed.manifest)
+                        .unwrap_or(false),
+                }
+            })
+            .collect()
+    }
+
+    pub fn runs_natively(&self, platform: Platform) -> bool {
+        self.platforms.contains(&platform)
+    }
+
+    /// Windows only games need Proton to run on Linux.
+    pub fn needs_proton(&self) -> bool {
+        !self.runs_natively(Platform::Linux) && self.runs_natively(Platform::Windows)
+    }
+
+    pub fn deck_category(&self) -> DeckCategory {
+        self.deck
+            .as_ref()
+            .map(|deck| deck.category)
+            .unwrap_or(DeckCategory::Unknown)
+    }
+
+    /// Titles are taken from `common/name_localized` when a `language`
+    /// (e.g. "japanese") is given and the app has a name in it.
     pub fn from(
-        app_infos: &Vec<AppInfo>,
-        pkg_infos: &Vec<PackageInfo>,
+        app_infos: &[AppInfo],
+        pkg_infos: &[PackageInfo],
+        language: Option<&str>,
     ) -> Result<Vec<SteamGame>, Error> {
-        let lib_folders_vdf =
-            fs::File::open("c:/program files (x86)/steam/steamapps/libraryfolders.vdf")?;
-        let mut lib_folders = Vec::new();
-        lib_folders.push(PathBuf::from("c:/program files (x86)/steam/steamapps/"));
-        for line in BufReader::new(lib_folders_vdf).lines() {
-            let mut line = line?;
-            line = line.trim().to_string();
-            let mut parts = line.split("\t").filter(|p| p.len() > 0);
-            let name = parts.next().unwrap().replace("\"", "");
-            if usize::from_str(&name).is_ok() {
-                let value = parts.next().unwrap().replace("\"", "");
-                lib_folders.push(PathBuf::from(value.replace("\\\\", "\\")).join("steamapps"));
-            }
-        }
+        let lib_folders: Vec<PathBuf> = LibraryFolder::load()?
+            .iter()
+            .map(LibraryFolder::steamapps)
+            .collect();
         eprintln!("Additional library folders to check: {:#?}", &lib_folders);
         let mut games = Vec::new();
-        let owned_games = {
-            let mut owned_games = Vec::<u32>::new();
-            for pkg_info in pkg_infos {
-                // let id = pkg_info.id;
-                let app_ids = pkg_info.map_entry(&["appids"]).unwrap();
-                if app_ids.keys().len() > 1 {
-                    // continue;
-                }
-                let game_id = pkg_info.u32_entry(&["appids", "0"]).unwrap();
-                owned_games.push(game_id);
-            }
-            owned_games
+        let packages_by_app = PackageInfo::packages_by_app(pkg_infos);
+        let pkg_infos_by_id: HashMap<u32, &PackageInfo> =
+            pkg_infos.iter().map(|p| (p.id, p)).collect();
+        let app_infos_by_id: HashMap<u32, &AppInfo> = app_infos
+            .iter()
+            .filter_map(|a| a.u32_entry(&["appinfo", "appid"]).map(|id| (id, a)))
+            .collect();
+        let licenses = Licenses::load().unwrap_or_else(|e| {
+            eprintln!("Unable to load license data: {}", e);
+            Licenses::default()
+        });
+        let user_grid = licenses.steam_id.map(|steam_id| {
+            steam_dir()
+                .join("userdata")
+                .join(account_id(steam_id).to_string())
+                .join("config/grid")
+        });
+        let ownership_of = |app_info: &AppInfo, app_id: u32, last_owner: Option<u64>| {
+            let granting_packages: Vec<&PackageInfo> = packages_by_app
+                .get(&app_id)
+                .map(|packages| {
+                    packages
+                        .iter()
+                        .filter_map(|id| pkg_infos_by_id.get(id).copied())
+                        .collect()
+                })
+                .unwrap_or_default();
+            licenses.ownership(app_info, &granting_packages, last_owner)
         };
         for app_info in app_infos {
             let app_id = app_info.u32_entry(&["appinfo", "appid"]).unwrap();
-            if !owned_games.contains(&app_id) {
-                continue;
-            }
+            let packages = match packages_by_app.get(&app_id) {
+                Some(packages) => packages.clone(),
+                None => continue,
+            };
             let name = app_info.string_entry(&["appinfo", "common", "name"]);
             if name.is_none() {
                 continue;
@@ -64,29 +191,90 @@ impl SteamGame {
             {
                 continue;
             }
-            let name = name.unwrap();
-            //let logo = app_info.string_entry(&["appinfo", "common", "logo"]);
-            let mut logo = Some(format!(
-                "c:/program files (x86)/steam/appcache/librarycache/{}_library_600x900.jpg",
-                app_id.to_string()
-            ));
-            if !PathBuf::from(logo.as_ref().unwrap()).exists() {
-                logo = None;
-            }
-            let mut installed = false;
-            for folder in &lib_folders {
-                if folder
-                    .join(format!("appmanifest_{}.acf", app_id.to_string()))
-                    .exists()
-                {
-                    installed = true;
-                }
+            let name = language
+                .and_then(|language| app_info.localized_name(language))
+                .unwrap_or_else(|| name.unwrap());
+            let artwork = Artwork::find(app_id, user_grid.as_deref());
+            let logo = artwork.capsule.clone();
+            let icon = app_info
+                .client_icon()
+                .map(|hash| icon_path(&hash))
+                .filter(|path| path.exists())
+                .map(|path| path.to_string_lossy().into_owned());
+            let manifest = AppManifest::find(&lib_folders, app_id);
+            let installed = match &manifest {
+                Some(manifest) => InstallState::Installed(manifest.state()),
+                None => InstallState::NotInstalled,
+            };
+            let install_path = manifest.as_ref().map(AppManifest::install_path);
+            let update_status = manifest.as_ref().map(|m| UpdateStatus::of(m, app_info));
+            let ownership = ownership_of(
+                app_info,
+                app_id,
+                manifest.as_ref().and_then(|m| m.last_owner),
+            );
+            let depots = app_info.depots();
+            let mut dlc = Vec::new();
+            for dlc_id in app_info.dlc() {
+                let dlc_info = app_infos_by_id.get(&dlc_id);
+                let owned = dlc_info
+                    .map(|dlc_info| ownership_of(dlc_info, dlc_id, None) != Ownership::Unknown)
+                    .unwrap_or(false);
+                // DLC without depots of its own only unlocks content that
+                // ships with the base game.
+                let dlc_depots: Vec<u32> = depots
+                    .iter()
+                    .filter(|d| d.dlc_app_id == Some(dlc_id))
+                    .map(|d| d.id)
+                    .collect();
+                let dlc_installed = match &manifest {
+                    None => false,
+                    Some(_) if dlc_depots.is_empty() => owned,
+                    Some(manifest) => manifest
+                        .installed_depots
+                        .iter()
+                        .any(|d| dlc_depots.contains(&d.depot_id)),
+                };
+                dlc.push(Dlc {
+                    id: dlc_id,
+                    title: dlc_info.and_then(|d| d.string_entry(&["appinfo", "common", "name"])),
+                    owned,
+                    installed: dlc_installed,
+                });
             }
             games.push(SteamGame {
                 id: app_id,
                 title: name,
                 logo,
+                icon,
+                artwork,
                 installed,
+                install_path,
+                manifest,
+                depots,
+                update_status,
+                packages,
+                ownership,
+                dlc,
+                store_tags: app_info.store_tags(),
+                genres: app_info.genres(),
+                primary_genre: app_info.primary_genre(),
+                categories: app_info.categories(),
+                deck: app_info.deck_compatibility(),
+                developers: app_info.associations("developer"),
+                publishers: app_info.associations("publisher"),
+                franchises: app_info.associations("franchise"),
+                release_date: app_info.release_date(),
+                original_release_date: app_info.original_release_date(),
+                metacritic_score: app_info.metacritic_score(),
+                review_score: app_info.review_score(),
+                review_percentage: app_info.review_percentage(),
+                languages: app_info.languages(),
+                platforms: app_info.platforms(),
+                os_arch: app_info.os_arch(),
+                controller_support: app_info.controller_support(),
+                steam_input: app_info.steam_input(),
+                vr: app_info.vr_support(),
             });
         }
         Ok(games)
@@ -0,0 +1,218 @@
+// Whether an installed app is out of sync with its branch. The appmanifest
+// records the installed `buildid` and any queued download, appinfo's
+// `depots/branches/<branch>/buildid` is the current build of the branch.
+// Like Steam, any mismatch counts, so rolled back branches need an update
+// too.
+
+use crate::app_info::AppInfo;
+use crate::app_manifest::AppManifest;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum UpdateStatus {
+    UpToDate,
+    // Steam already knows about the update and has (part of) it queued.
+    UpdateQueued {
+        bytes_to_download: u64,
+    },
+    // The branch's build differs from the one installed. The download
+    // size is estimated from the depots whose manifest changed.
+    UpdateAvailable {
+        installed_build: u32,
+        latest_build: u32,
+        estimated_download: Option<u64>,
+    },
+    Unknown,
+}
+
+fn estimate_download(manifest: &AppManifest, app_info: &AppInfo) -> Option<u64> {
+    let depots = app_info.depots();
+    let mut total = None;
+    for installed in &manifest.installed_depots {
+        let latest = depots
+            .iter()
+            .find(|d| d.id == installed.depot_id)
+            .and_then(|d| d.manifests.get(manifest.branch()));
+        if let Some(latest) = latest {
+            if latest.gid != installed.manifest {
+                let size = latest.download.or(latest.size).unwrap_or(0);
+                total = Some(total.unwrap_or(0) + size);
+            }
+        }
+    }
+    total
+}
+
+impl UpdateStatus {
+    pub fn of(manifest: &AppManifest, app_info: &AppInfo) -> UpdateStatus {
+        if manifest.download_pending() {
+            return UpdateStatus::UpdateQueued {
+                bytes_to_download: manifest
+                    .bytes_to_download
+                    .saturating_sub(manifest.bytes_downloaded),
+            };
+        }
+        let latest_build = app_info
+            .branches()
+            .into_iter()
+            .find(|branch| branch.name == manifest.branch())
+            .map(|branch| branch.build_id)
+            .filter(|build_id| *build_id != 0);
+        match latest_build {
+            None => UpdateStatus::Unknown,
+            Some(latest_build) if latest_build != manifest.build_id => {
+                UpdateStatus::UpdateAvailable {
+                    installed_build: manifest.build_id,
+                    latest_build,
+                    estimated_download: estimate_download(manifest, app_info),
+                }
+            }
+            Some(_) => UpdateStatus::UpToDate,
+        }
+    }
+
+    pub fn needs_update(&self) -> bool {
+        matches!(
+            self,
+            UpdateStatus::UpdateQueued { .. } | UpdateStatus::UpdateAvailable { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_info::Property;
+    use crate::vdf::{self, Vdf};
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    fn props(vdf: &HashMap<String, vdf::Property>) -> HashMap<String, Property> {
+        vdf.iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    vdf::Property::Map(map) => Property::Map(props(map)),
+                    vdf::Property::String(string) => Property::String(string.clone()),
+                };
+                (key.clone(), value)
+            })
+            .collect()
+    }
+
+    fn app_info() -> AppInfo {
+        let vdf = Vdf::parse(
+            r#"
+            "appinfo"
+            {
+                "appid" "10"
+                "depots"
+                {
+                    "11"
+                    {
+                        "manifests"
+                        {
+                            "public" { "gid" "555" "size" "1000" "download" "400" }
+                            "beta" { "gid" "556" "size" "1000" "download" "500" }
+                        }
+                    }
+                    "12"
+                    {
+                        "manifests" { "public" { "gid" "600" "size" "50" } }
+                    }
+                    "branches"
+                    {
+                        "public" { "buildid" "300" }
+                        "beta" { "buildid" "310" }
+                    }
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        AppInfo {
+            state: 2,
+            last_updated: 0,
+            access_token: 0,
+            checksum: [0; 20],
+            change_no: 0,
+            props: props(&vdf.props),
+        }
+    }
+
+    fn manifest(build_id: u32, extra: &str) -> AppManifest {
+        let acf = Vdf::parse(&format!(
+            r#"
+            "AppState"
+            {{
+                "appid" "10"
+                "installdir" "Game"
+                "StateFlags" "4"
+                "buildid" "{}"
+                "InstalledDepots"
+                {{
+                    "11" {{ "manifest" "554" "size" "1000" }}
+                    "12" {{ "manifest" "600" "size" "50" }}
+                }}
+                {}
+            }}
+            "#,
+            build_id, extra
+        ))
+        .unwrap();
+        AppManifest::parse(&acf, Path::new("appmanifest_10.acf")).unwrap()
+    }
+
+    #[test]
+    fn queued_download() {
+        let manifest = manifest(200, r#""BytesToDownload" "100" "BytesDownloaded" "40""#);
+        let status = UpdateStatus::of(&manifest, &app_info());
+        assert_eq!(
+            status,
+            UpdateStatus::UpdateQueued {
+                bytes_to_download: 60
+            }
+        );
+        assert!(status.needs_update());
+    }
+
+    #[test]
+    fn build_mismatch() {
+        let app_info = app_info();
+        for build_id in [200, 400] {
+            assert_eq!(
+                UpdateStatus::of(&manifest(build_id, ""), &app_info),
+                UpdateStatus::UpdateAvailable {
+                    installed_build: build_id,
+                    latest_build: 300,
+                    estimated_download: Some(400),
+                }
+            );
+        }
+        let status = UpdateStatus::of(&manifest(300, ""), &app_info);
+        assert_eq!(status, UpdateStatus::UpToDate);
+        assert!(!status.needs_update());
+    }
+
+    #[test]
+    fn branches() {
+        let app_info = app_info();
+        let beta = r#""UserConfig" { "betakey" "beta" }"#;
+        assert_eq!(
+            UpdateStatus::of(&manifest(310, beta), &app_info),
+            UpdateStatus::UpToDate
+        );
+        assert_eq!(
+            UpdateStatus::of(&manifest(300, beta), &app_info),
+            UpdateStatus::UpdateAvailable {
+                installed_build: 300,
+                latest_build: 310,
+                estimated_download: Some(500),
+            }
+        );
+        let gone = r#""UserConfig" { "betakey" "removed" }"#;
+        assert_eq!(
+            UpdateStatus::of(&manifest(300, gone), &app_info),
+            UpdateStatus::Unknown
+        );
+    }
+}
@@ -0,0 +1,259 @@
+// Parser for the text flavor of VDF (Valve's KeyValues format) used by
+// libraryfolders.vdf, appmanifest_*.acf, loginusers.vdf, localconfig.vdf etc.
+//
+//   "AppState"
+//   {
+//       "appid"     "220"
+//       "installdir"    "Half-Life 2"
+//   }
+//
+// Keys are case insensitive in practice ("LibraryFolders" vs
+// "libraryfolders"), so lookups fall back to a case insensitive match.
+
+use anyhow::{anyhow, Error};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum Property {
+    Map(HashMap<String, Property>),
+    String(String),
+}
+
+#[derive(Debug)]
+pub struct Vdf {
+    pub props: HashMap<String, Property>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Text(String),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        None => return Err(anyhow!("Unterminated string: {}", value)),
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some('t') => value.push('\t'),
+                            Some(escaped) => value.push(escaped),
+                            None => return Err(anyhow!("Unterminated string: {}", value)),
+                        },
+                        Some(c) => value.push(c),
+                    }
+                }
+                tokens.push(Token::Text(value));
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                // comment
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '[' => {
+                // conditional like [$WIN32], ignored
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                }
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                // unquoted token
+                let mut value = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '{' || c == '}' || c == '"' {
+                        break;
+                    }
+                    value.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Text(value));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_map(
+    tokens: &mut std::vec::IntoIter<Token>,
+    nested: bool,
+) -> Result<HashMap<String, Property>, Error> {
+    let mut props = HashMap::new();
+    loop {
+        let key = match tokens.next() {
+            None if nested => return Err(anyhow!("Unexpected end of file")),
+            None => break,
+            Some(Token::Close) if nested => break,
+            Some(Token::Close) => return Err(anyhow!("Unexpected '}}'")),
+            Some(Token::Open) => return Err(anyhow!("Unexpected '{{'")),
+            Some(Token::Text(key)) => key,
+        };
+        match tokens.next() {
+            Some(Token::Open) => {
+                props.insert(key, Property::Map(parse_map(tokens, true)?));
+            }
+            Some(Token::Text(value)) => {
+                props.insert(key, Property::String(value));
+            }
+            _ => return Err(anyhow!("Missing value for key: {}", key)),
+        }
+    }
+    Ok(props)
+}
+
+fn get<'a>(props: &'a HashMap<String, Property>, key: &str) -> Option<&'a Property> {
+    props.get(key).or_else(|| {
+        props
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    })
+}
+
+impl Vdf {
+    pub fn load(path: &Path) -> Result<Vdf, Error> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Unable to read {}: {}", path.display(), e))?;
+        Vdf::parse(&text).map_err(|e| anyhow!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Vdf, Error> {
+        let mut tokens = tokenize(text)?.into_iter();
+        Ok(Vdf {
+            props: parse_map(&mut tokens, false)?,
+        })
+    }
+
+    pub fn entry(&self, path: &[&str]) -> Option<&Property> {
+        let mut props = &self.props;
+        let mut value = None;
+        let mut terminal = false;
+        for segment in path {
+            if terminal {
+                // We've reached a terminal property before reaching the
+                // last path segment.
+                return None;
+            }
+            value = get(props, segment);
+            match value? {
+                Property::Map(nested_props) => props = nested_props,
+                _ => terminal = true,
+            }
+        }
+        value
+    }
+
+    pub fn map_entry(&self, path: &[&str]) -> Option<&HashMap<String, Property>> {
+        match self.entry(path) {
+            Some(Property::Map(map)) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn string_entry(&self, path: &[&str]) -> Option<String> {
+        match self.entry(path) {
+            Some(Property::String(string)) => Some(string.to_owned()),
+            _ => None,
+        }
+    }
+
+    pub fn u32_entry(&self, path: &[&str]) -> Option<u32> {
+        self.string_entry(path)
+            .and_then(|s| u32::from_str(s.trim()).ok())
+    }
+
+    pub fn u64_entry(&self, path: &[&str]) -> Option<u64> {
+        self.string_entry(path)
+            .and_then(|s| u64::from_str(s.trim()).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_maps() {
+        let vdf = Vdf::parse(
+            r#"
+            "AppState"
+            {
+                "appid"		"220"
+                "installdir"		"Half-Life 2"
+                "UserConfig"
+                {
+                    "language"		"english"
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(vdf.u32_entry(&["AppState", "appid"]), Some(220));
+        assert_eq!(
+            vdf.string_entry(&["AppState", "installdir"]),
+            Some("Half-Life 2".to_string())
+        );
+        assert_eq!(
+            vdf.string_entry(&["appstate", "userconfig", "language"]),
+            Some("english".to_string())
+        );
+        assert!(vdf.map_entry(&["AppState", "UserConfig"]).is_some());
+        assert!(vdf.entry(&["AppState", "appid", "nested"]).is_none());
+    }
+
+    #[test]
+    fn handles_escapes_comments_and_conditionals() {
+        let vdf = Vdf::parse(
+            r#"
+            // leading comment
+            "root"
+            {
+                "path"	"C:\\Program Files (x86)\\Steam" // trailing comment
+                "quote"	"say \"hi\"\tthere\n"
+                "win"	"1"	[$WIN32]
+                unquoted	value
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            vdf.string_entry(&["root", "path"]),
+            Some(r"C:\Program Files (x86)\Steam".to_string())
+        );
+        assert_eq!(
+            vdf.string_entry(&["root", "quote"]),
+            Some("say \"hi\"\tthere\n".to_string())
+        );
+        assert_eq!(vdf.u32_entry(&["root", "win"]), Some(1));
+        assert_eq!(
+            vdf.string_entry(&["root", "unquoted"]),
+            Some("value".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(Vdf::parse(r#""root" { "key" "value""#).is_err());
+        assert!(Vdf::parse(r#""root" { "key" }"#).is_err());
+        assert!(Vdf::parse(r#""key" "unterminated"#).is_err());
+        assert!(Vdf::parse("}").is_err());
+    }
+}
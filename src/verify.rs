@@ -0,0 +1,134 @@
+// Offline equivalent of "Verify integrity of game files": compare an
+// installed app's files with the depot manifests of its installed depots.
+
+use crate::app_manifest::AppManifest;
+use crate::depot_manifest::{DepotFileFlags, DepotManifest};
+use anyhow::{anyhow, Error};
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Serialize)]
+pub struct VerifyReport {
+    pub verified: usize,
+    pub missing: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>,
+    // Only filled in when the manifests of all installed depots were found,
+    // otherwise every file of the missing depots would show up here. Saves,
+    // logs and generated configs end up here too, so like Steam's own
+    // verify these do not fail the check.
+    pub extra: Vec<PathBuf>,
+    pub missing_manifests: Vec<u32>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.modified.is_empty() && self.missing_manifests.is_empty()
+    }
+}
+
+fn sha1_of(path: &Path) -> Result<Vec<u8>, Error> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha1::new();
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+// Manifests don't preserve case the way the disk does on Windows.
+fn key(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/").to_lowercase()
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            walk(&entry.path(), files)?;
+        } else {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// `lib_folders` are the steamapps folders to search for depotcache
+/// manifests.
+pub fn verify(manifest: &AppManifest, lib_folders: &[PathBuf]) -> Result<VerifyReport, Error> {
+    let install_path = manifest.install_path();
+    if !install_path.exists() {
+        return Err(anyhow!("{} does not exist", install_path.display()));
+    }
+    let mut report = VerifyReport::default();
+    let mut expected = HashSet::new();
+    for depot in &manifest.installed_depots {
+        let path = match DepotManifest::find(lib_folders, depot.depot_id, depot.manifest) {
+            Some(path) => path,
+            None => {
+                report.missing_manifests.push(depot.depot_id);
+                continue;
+            }
+        };
+        let depot_manifest = DepotManifest::load(&path)?;
+        if depot_manifest.filenames_encrypted {
+            return Err(anyhow!(
+                "The file names in {} are encrypted",
+                path.display()
+            ));
+        }
+        for file in &depot_manifest.files {
+            let relative = file.path();
+            expected.insert(key(&relative));
+            if file
+                .flags
+                .intersects(DepotFileFlags::DIRECTORY | DepotFileFlags::SYMLINK)
+            {
+                continue;
+            }
+            let path = install_path.join(&relative);
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(_) => {
+                    report.missing.push(relative);
+                    continue;
+                }
+            };
+            // User config files are expected to change after install.
+            let user_config = file
+                .flags
+                .intersects(DepotFileFlags::USER_CONFIG | DepotFileFlags::VERSIONED_USER_CONFIG);
+            if user_config {
+                report.verified += 1;
+                continue;
+            }
+            if metadata.len() != file.size
+                || (!file.sha_content.is_empty() && sha1_of(&path)? != file.sha_content)
+            {
+                report.modified.push(relative);
+            } else {
+                report.verified += 1;
+            }
+        }
+    }
+    if report.missing_manifests.is_empty() {
+        let mut files = Vec::new();
+        walk(&install_path, &mut files)?;
+        for file in files {
+            let relative = file.strip_prefix(&install_path)?.to_path_buf();
+            if !expected.contains(&key(&relative)) {
+                report.extra.push(relative);
+            }
+        }
+        report.extra.sort();
+    }
+    Ok(report)
+}
@@ -0,0 +1,20 @@
+// VR support from appinfo's `common/openvrsupport`, `common/oculussupport`
+// and the VR store categories.
+
+use serde::Serialize;
+
+#[derive(Debug, Default, Serialize)]
+pub struct VrSupport {
+    pub openvr: bool,
+    pub oculus: bool,
+    // Store category "VR Support" or "VR Supported".
+    pub vr_supported: bool,
+    pub tracked_controllers: bool,
+    pub vr_only: bool,
+}
+
+impl VrSupport {
+    pub fn is_vr(&self) -> bool {
+        self.openvr || self.oculus || self.vr_supported || self.tracked_controllers || self.vr_only
+    }
+}
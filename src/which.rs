@@ -0,0 +1,163 @@
+// Reverse lookup from a path on disk to the app it belongs to.
+//
+// Per library folder, Steam keeps app data in:
+//   steamapps/common/<installdir>          - the install, see appmanifest
+//   steamapps/workshop/content/<appid>     - workshop items
+//   steamapps/compatdata/<appid>           - Proton prefix
+//   steamapps/shadercache/<appid>          - shader cache
+// and, in the Steam directory,
+//   userdata/<account id>/760/remote/<appid>/screenshots
+
+use crate::app_info::AppInfo;
+use crate::depot_manifest::DepotManifest;
+use crate::library_folder::LibraryFolder;
+use crate::steam_dir::steam_dir;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum DataKind {
+    Install,
+    Workshop,
+    Prefix,
+    ShaderCache,
+    Screenshot,
+}
+
+// Path components, lower cased, so Windows paths compare the way Windows
+// does. Canonicalized Windows paths carry a \\?\ prefix, which is dropped.
+fn components(path: &Path) -> Vec<String> {
+    path.to_string_lossy()
+        .trim_start_matches(r"\\?\")
+        .replace('\\', "/")
+        .split('/')
+        .filter(|c| !c.is_empty() && *c != ".")
+        .map(|c| c.to_lowercase())
+        .collect()
+}
+
+// The components of `path` below `base`, if it is below it. `path` is
+// canonical, so `base` is too when it exists (~/.steam/steam is a link).
+fn relative(path: &[String], base: &Path) -> Option<Vec<String>> {
+    let base = components(&fs::canonicalize(base).unwrap_or_else(|_| base.to_path_buf()));
+    if path.len() < base.len() || path[..base.len()] != base[..] {
+        return None;
+    }
+    Some(path[base.len()..].to_vec())
+}
+
+fn app_id_dir(path: &[String], base: &Path) -> Option<u32> {
+    relative(path, base)?
+        .first()
+        .and_then(|id| u32::from_str(id).ok())
+}
+
+// An install directory without an appmanifest. Match the directory against
+// appinfo's `config/installdir`.
+fn from_install_dir(install_dir: &str, app_infos: &[AppInfo]) -> Option<u32> {
+    app_infos
+        .iter()
+        .find(|a| {
+            a.string_entry(&["appinfo", "config", "installdir"])
+                .map(|dir| dir.to_lowercase() == install_dir)
+                .unwrap_or(false)
+        })
+        .and_then(|a| a.u32_entry(&["appinfo", "appid"]))
+}
+
+// Failing that, find the file in a depot manifest and map the depot back to
+// its app.
+fn from_depot_manifests(
+    remainder: &[String],
+    folder: &LibraryFolder,
+    app_infos: &[AppInfo],
+) -> Option<u32> {
+    if remainder.is_empty() {
+        return None;
+    }
+    let wanted = remainder.join("/");
+    let dirs = [
+        steam_dir().join("depotcache"),
+        folder.steamapps().join("depotcache"),
+    ];
+    for dir in dirs.iter() {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let manifest = match DepotManifest::load(&entry.path()) {
+                Ok(manifest) => manifest,
+                Err(_) => continue,
+            };
+            let found = manifest
+                .files
+                .iter()
+                .any(|f| components(&f.path()).join("/") == wanted);
+            if !found {
+                continue;
+            }
+            let owner = app_infos.iter().find(|a| {
+                a.depots()
+                    .iter()
+                    .any(|d| d.id == manifest.depot_id && d.depot_from_app.is_none())
+            });
+            if let Some(owner) = owner {
+                return owner.u32_entry(&["appinfo", "appid"]);
+            }
+        }
+    }
+    None
+}
+
+/// Find the app that `path` belongs to and what kind of data it is. `path`
+/// should be canonical.
+pub fn which(
+    path: &Path,
+    folders: &[LibraryFolder],
+    app_infos: &[AppInfo],
+) -> Option<(u32, DataKind)> {
+    let path = components(path);
+    for folder in folders {
+        let steamapps = folder.steamapps();
+        if let Some(remainder) = relative(&path, &steamapps.join("common")) {
+            let install_dir = remainder.first()?;
+            let manifests = folder.manifests().unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                Vec::new()
+            });
+            let manifest = manifests
+                .into_iter()
+                .find(|m| m.install_dir.to_lowercase() == *install_dir);
+            if let Some(manifest) = manifest {
+                return Some((manifest.app_id, DataKind::Install));
+            }
+            return from_install_dir(install_dir, app_infos)
+                .or_else(|| from_depot_manifests(&remainder[1..], folder, app_infos))
+                .map(|app_id| (app_id, DataKind::Install));
+        }
+        let kinds = [
+            ("workshop/content", DataKind::Workshop),
+            ("workshop/downloads", DataKind::Workshop),
+            ("compatdata", DataKind::Prefix),
+            ("shadercache", DataKind::ShaderCache),
+        ];
+        for (dir, kind) in kinds.iter() {
+            if let Some(app_id) = app_id_dir(&path, &steamapps.join(dir)) {
+                return Some((app_id, *kind));
+            }
+        }
+    }
+    let userdata = steam_dir().join("userdata");
+    if let Some(remainder) = relative(&path, &userdata) {
+        // <account id>/760/remote/<appid>/screenshots
+        if remainder.len() >= 4 && remainder[1] == "760" && remainder[2] == "remote" {
+            if let Ok(app_id) = u32::from_str(&remainder[3]) {
+                return Some((app_id, DataKind::Screenshot));
+            }
+        }
+    }
+    None
+}